@@ -27,11 +27,38 @@ pub enum Command {
         #[command(subcommand)]
         action: PomodoroAction,
     },
+    /// Browse #tags and @contexts
+    Tags {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+    /// Deleted tasks and projects
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
     /// Add a note to a task
     #[command(visible_alias = "n")]
     Note {
         /// The note text
         text: String,
+        /// ID of the task to annotate (prompts if omitted)
+        #[arg(long)]
+        task: Option<String>,
+    },
+    /// Search tasks, notes, projects and the archive
+    #[command(visible_alias = "s")]
+    Search {
+        /// Words that must all appear, ignoring case
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Revert the last changes made to your tasks and projects
+    #[command(visible_alias = "u")]
+    Undo {
+        /// How many operations to revert
+        #[arg(default_value_t = 1)]
+        count: usize,
     },
 }
 
@@ -42,16 +69,173 @@ pub enum TaskAction {
     Add {
         /// The task description
         description: String,
+        /// Due date, e.g. 2026-10-20, tomorrow, friday, "in 3 days"
+        #[arg(long)]
+        due: Option<String>,
+        /// Date you plan to work on the task
+        #[arg(long)]
+        scheduled: Option<String>,
+        /// Priority: A (highest), B or C
+        #[arg(long)]
+        priority: Option<String>,
+        /// Repeat after completion, e.g. week, 2d, "month on 1st"
+        #[arg(long)]
+        every: Option<String>,
+        /// Add as a subtask of the task with this ID
+        #[arg(long)]
+        parent: Option<String>,
+        /// Expected number of pomodoros
+        #[arg(long)]
+        estimate: Option<usize>,
+        /// ID or description of a task that must be done first (repeatable)
+        #[arg(long)]
+        blocked_by: Vec<String>,
     },
     /// List open tasks
     #[command(visible_alias = "l")]
-    List,
+    List {
+        /// List completed tasks instead
+        #[arg(long)]
+        done: bool,
+        /// With --done, only tasks finished on or after this date
+        #[arg(long, requires = "done")]
+        since: Option<String>,
+        /// Only tasks past their due date
+        #[arg(long, conflicts_with_all = ["today", "week"])]
+        overdue: bool,
+        /// Only tasks due or scheduled today (or earlier)
+        #[arg(long, conflicts_with = "week")]
+        today: bool,
+        /// Only tasks due or scheduled within the next seven days (or earlier)
+        #[arg(long)]
+        week: bool,
+        /// Only tasks with this #tag (repeatable)
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Only tasks with this @context (repeatable)
+        #[arg(long)]
+        context: Vec<String>,
+        /// Only tasks in this project and its sub-projects, e.g. Work/Backend
+        #[arg(long)]
+        project: Option<String>,
+        /// List snoozed tasks instead
+        #[arg(long, conflicts_with = "done")]
+        snoozed: bool,
+    },
     /// Mark a task as complete
     #[command(visible_alias = "c")]
-    Complete,
-    /// Delete a task
+    Complete {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Compare estimated and actual pomodoros of completed tasks
+    Estimates {
+        /// Only tasks in this project and its sub-projects
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// List tasks handed off to someone else
+    Waiting,
+    /// Mark a task as handed off to someone else, e.g. "review PR @alice"
+    Wait {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Take back a task that was waiting on someone else
+    Resume {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Hide a task from listings and pickers until a later day
+    Snooze {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+        /// Day to show it again, e.g. monday, "next week", 2026-11-01, or "none"
+        #[arg(long, default_value = "tomorrow")]
+        until: String,
+    },
+    /// Mark a task as waiting for another task to be done
+    Block {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+        /// ID or description of the task it waits for
+        #[arg(long)]
+        by: String,
+    },
+    /// Remove a task's dependencies on other tasks
+    Unblock {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+        /// Only the dependency on this task (ID or description)
+        #[arg(long)]
+        by: Option<String>,
+    },
+    /// Mark a completed task as open again
+    Reopen {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Move a task to the trash
     #[command(visible_alias = "d")]
-    Delete,
+    Delete {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Edit a task's description and metadata, keeping its sub-items
+    #[command(visible_alias = "e")]
+    Edit {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+        /// Open the task in $EDITOR instead of editing it inline
+        #[arg(long)]
+        editor: bool,
+    },
+    /// Move a task and its sub-items to another project
+    #[command(visible_alias = "m")]
+    Move {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+        /// Target project (prompts if omitted)
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Move a task to a position among the open tasks of its project
+    Reorder {
+        /// 1 for the top of its project (or parent task)
+        position: usize,
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Move a task to the top of its project
+    Top {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Move a task to the bottom of its project
+    Bottom {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+    /// Move completed tasks to archive.md
+    Archive {
+        /// Only tasks completed longer ago than this, e.g. 14d, 2w, 1m
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+    /// Set or clear the priority of a task
+    Prioritize {
+        /// A (highest), B, C, or "none" to clear
+        priority: String,
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// List tags and contexts with their open task counts
+    #[command(visible_alias = "l")]
+    List,
 }
 
 #[derive(Subcommand)]
@@ -63,15 +247,73 @@ pub enum ProjectAction {
         /// The project name
         name: String,
     },
-    /// Delete a project and all its todos
+    /// Delete a project, moving its todos to the trash
     Delete,
+    /// Rename a project, also in the archive and trash
+    Rename {
+        /// Current project name
+        old: String,
+        /// New project name
+        new: String,
+    },
+    /// Move a project to a position among its sibling projects
+    Reorder {
+        /// The project name
+        name: String,
+        /// 1 for the first project (or sub-project of its parent)
+        position: usize,
+    },
+    /// Set a project's description, status, colour or pomodoro length
+    Set {
+        /// The project name
+        name: String,
+        /// A short description, or "" to clear
+        #[arg(long)]
+        description: Option<String>,
+        /// active, paused, done, archived, or "none" to clear
+        #[arg(long)]
+        status: Option<String>,
+        /// red, green, yellow, blue, magenta, cyan, or "none" to clear
+        #[arg(long)]
+        colour: Option<String>,
+        /// Pomodoro length in minutes for the project's tasks, 0 to clear
+        #[arg(long)]
+        pomodoro: Option<u32>,
+    },
+    /// Hide a project from task listings and pickers without deleting it
+    Archive {
+        /// The project name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TrashAction {
+    /// List deleted tasks
+    #[command(visible_alias = "l")]
+    List,
+    /// Put deleted tasks back where they were
+    #[command(visible_alias = "r")]
+    Restore {
+        /// Task ID (prompts if omitted)
+        id: Option<String>,
+        /// Restore every task deleted from this project
+        #[arg(long, conflicts_with = "id")]
+        project: Option<String>,
+    },
+    /// Permanently remove everything in the trash
+    Empty,
 }
 
 #[derive(Subcommand)]
 pub enum PomodoroAction {
-    /// Start a 25-minute pomodoro timer
+    /// Start a pomodoro timer (25 minutes, or as set on the task's project)
     #[command(visible_alias = "s")]
-    Start,
+    Start {
+        /// ID of the task to focus on (prompts if omitted)
+        #[arg(long)]
+        task: Option<String>,
+    },
 }
 
 #[cfg(test)]
@@ -91,7 +333,7 @@ mod tests {
         let cli = Cli::parse_from(["ambrogio", "tasks", "add", "buy milk"]);
         match cli.command {
             Some(Command::Tasks {
-                action: TaskAction::Add { description },
+                action: TaskAction::Add { description, .. },
             }) => assert_eq!(description, "buy milk"),
             _ => panic!("expected Tasks Add"),
         }
@@ -103,18 +345,381 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Command::Tasks {
-                action: TaskAction::List
+                action: TaskAction::List { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_add_with_dates() {
+        let cli = Cli::parse_from([
+            "ambrogio",
+            "tasks",
+            "add",
+            "release",
+            "--due",
+            "tomorrow",
+            "--scheduled",
+            "2026-10-18",
+        ]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Add { due, scheduled, .. },
+            }) => {
+                assert_eq!(due.as_deref(), Some("tomorrow"));
+                assert_eq!(scheduled.as_deref(), Some("2026-10-18"));
+            }
+            _ => panic!("expected Tasks Add"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_add_with_priority() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "add", "ship", "--priority", "A"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Add { priority, .. },
+            }) => assert_eq!(priority.as_deref(), Some("A")),
+            _ => panic!("expected Tasks Add"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_add_with_recurrence() {
+        let cli = Cli::parse_from([
+            "ambrogio",
+            "tasks",
+            "add",
+            "rent",
+            "--every",
+            "month on 1st",
+        ]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Add { every, .. },
+            }) => assert_eq!(every.as_deref(), Some("month on 1st")),
+            _ => panic!("expected Tasks Add"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_add_with_parent() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "add", "step", "--parent", "k3f9"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Add { parent, .. },
+            }) => assert_eq!(parent.as_deref(), Some("k3f9")),
+            _ => panic!("expected Tasks Add"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_estimates() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "add", "spec", "--estimate", "4"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Add { estimate, .. },
+            }) => assert_eq!(estimate, Some(4)),
+            _ => panic!("expected Tasks Add"),
+        }
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "estimates", "--project", "Work"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Estimates { project: Some(p) },
+            }) if p == "Work"
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_waiting_wait_and_resume() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "waiting"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Waiting,
+            })
+        ));
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "wait", "ab12"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Wait { id: Some(_) },
+            })
+        ));
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "resume"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Resume { id: None },
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_snooze() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "snooze", "ab12", "--until", "monday"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Snooze { id, until },
+            }) => {
+                assert_eq!(id.as_deref(), Some("ab12"));
+                assert_eq!(until, "monday");
+            }
+            _ => panic!("expected Tasks Snooze"),
+        }
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "snooze"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Snooze { id: None, until },
+            }) if until == "tomorrow"
+        ));
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "list", "--snoozed"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::List { snoozed: true, .. },
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_block_and_unblock() {
+        let cli = Cli::parse_from([
+            "ambrogio",
+            "tasks",
+            "add",
+            "deploy",
+            "--blocked-by",
+            "k3f9",
+            "--blocked-by",
+            "write spec",
+        ]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Add { blocked_by, .. },
+            }) => assert_eq!(blocked_by, vec!["k3f9", "write spec"]),
+            _ => panic!("expected Tasks Add"),
+        }
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "block", "ab12", "--by", "k3f9"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Block { id, by },
+            }) => {
+                assert_eq!(id.as_deref(), Some("ab12"));
+                assert_eq!(by, "k3f9");
+            }
+            _ => panic!("expected Tasks Block"),
+        }
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "unblock"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Unblock { id: None, by: None },
             })
         ));
     }
 
+    #[test]
+    fn parses_tasks_edit() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "edit", "k3f9", "--editor"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Edit { id, editor },
+            }) => {
+                assert_eq!(id.as_deref(), Some("k3f9"));
+                assert!(editor);
+            }
+            _ => panic!("expected Tasks Edit"),
+        }
+    }
+
+    #[test]
+    fn alias_t_e_parses_as_tasks_edit() {
+        let cli = Cli::parse_from(["ambrogio", "t", "e"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Edit {
+                    id: None,
+                    editor: false
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_move() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "move", "k3f9", "--to", "Home"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Move { id, to },
+            }) => {
+                assert_eq!(id.as_deref(), Some("k3f9"));
+                assert_eq!(to.as_deref(), Some("Home"));
+            }
+            _ => panic!("expected Tasks Move"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_list_done_since() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "list", "--done", "--since", "monday"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::List { done, since, .. },
+            }) => {
+                assert!(done);
+                assert_eq!(since.as_deref(), Some("monday"));
+            }
+            _ => panic!("expected Tasks List"),
+        }
+    }
+
+    #[test]
+    fn rejects_since_without_done() {
+        let result = Cli::try_parse_from(["ambrogio", "tasks", "list", "--since", "monday"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parses_tasks_reopen() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "reopen", "k3f9"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Reopen { id: Some(ref id) }
+            }) if id == "k3f9"
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_archive() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "archive", "--older-than", "14d"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Archive { older_than },
+            }) => assert_eq!(older_than.as_deref(), Some("14d")),
+            _ => panic!("expected Tasks Archive"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_prioritize() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "prioritize", "B", "ab12"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Prioritize { priority, id },
+            }) => {
+                assert_eq!(priority, "B");
+                assert_eq!(id.as_deref(), Some("ab12"));
+            }
+            _ => panic!("expected Tasks Prioritize"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_reorder_top_and_bottom() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "reorder", "2", "ab12"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Reorder { position, id },
+            }) => {
+                assert_eq!(position, 2);
+                assert_eq!(id.as_deref(), Some("ab12"));
+            }
+            _ => panic!("expected Tasks Reorder"),
+        }
+
+        let cli = Cli::parse_from(["ambrogio", "t", "top"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Top { id: None },
+            })
+        ));
+
+        let cli = Cli::parse_from(["ambrogio", "tasks", "bottom", "ab12"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::Bottom { id: Some(_) },
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_list_date_filters() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "list", "--overdue"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tasks {
+                action: TaskAction::List {
+                    overdue: true,
+                    today: false,
+                    week: false,
+                    ..
+                }
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_tasks_list_tag_and_context_filters() {
+        let cli = Cli::parse_from([
+            "ambrogio",
+            "tasks",
+            "list",
+            "--tag",
+            "infra",
+            "--tag",
+            "ci",
+            "--context",
+            "office",
+        ]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::List { tag, context, .. },
+            }) => {
+                assert_eq!(tag, vec!["infra", "ci"]);
+                assert_eq!(context, vec!["office"]);
+            }
+            _ => panic!("expected Tasks List"),
+        }
+    }
+
+    #[test]
+    fn parses_tags_list() {
+        let cli = Cli::parse_from(["ambrogio", "tags", "list"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Tags {
+                action: TagAction::List
+            })
+        ));
+    }
+
+    #[test]
+    fn rejects_conflicting_list_date_filters() {
+        let result = Cli::try_parse_from(["ambrogio", "tasks", "list", "--today", "--week"]);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn parses_tasks_complete() {
         let cli = Cli::parse_from(["ambrogio", "tasks", "complete"]);
         assert!(matches!(
             cli.command,
             Some(Command::Tasks {
-                action: TaskAction::Complete
+                action: TaskAction::Complete { id: None }
             })
         ));
     }
@@ -125,20 +730,104 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Command::Tasks {
-                action: TaskAction::Delete
+                action: TaskAction::Delete { id: None }
             })
         ));
     }
 
+    #[test]
+    fn parses_tasks_complete_with_id() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "complete", "ab12"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Complete { id },
+            }) => assert_eq!(id.as_deref(), Some("ab12")),
+            _ => panic!("expected Tasks Complete"),
+        }
+    }
+
+    #[test]
+    fn parses_tasks_delete_with_id() {
+        let cli = Cli::parse_from(["ambrogio", "tasks", "delete", "ab12"]);
+        match cli.command {
+            Some(Command::Tasks {
+                action: TaskAction::Delete { id },
+            }) => assert_eq!(id.as_deref(), Some("ab12")),
+            _ => panic!("expected Tasks Delete"),
+        }
+    }
+
+    #[test]
+    fn parses_note_with_task_id() {
+        let cli = Cli::parse_from(["ambrogio", "note", "text", "--task", "ab12"]);
+        match cli.command {
+            Some(Command::Note { text, task }) => {
+                assert_eq!(text, "text");
+                assert_eq!(task.as_deref(), Some("ab12"));
+            }
+            _ => panic!("expected Note"),
+        }
+    }
+
     #[test]
     fn parses_note() {
         let cli = Cli::parse_from(["ambrogio", "note", "some note text"]);
         match cli.command {
-            Some(Command::Note { text }) => assert_eq!(text, "some note text"),
+            Some(Command::Note { text, task }) => {
+                assert_eq!(text, "some note text");
+                assert!(task.is_none());
+            }
             _ => panic!("expected Note"),
         }
     }
 
+    #[test]
+    fn parses_trash_commands() {
+        let cli = Cli::parse_from(["ambrogio", "trash", "l"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Trash {
+                action: TrashAction::List
+            })
+        ));
+
+        let cli = Cli::parse_from(["ambrogio", "trash", "restore", "--project", "Work"]);
+        match cli.command {
+            Some(Command::Trash {
+                action: TrashAction::Restore { id, project },
+            }) => {
+                assert!(id.is_none());
+                assert_eq!(project.as_deref(), Some("Work"));
+            }
+            _ => panic!("expected Trash Restore"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["ambrogio", "trash", "restore", "ab12", "--project", "Work"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_search_words() {
+        let cli = Cli::parse_from(["ambrogio", "s", "release", "notes"]);
+        match cli.command {
+            Some(Command::Search { query }) => assert_eq!(query, vec!["release", "notes"]),
+            _ => panic!("expected Search"),
+        }
+
+        assert!(Cli::try_parse_from(["ambrogio", "search"]).is_err());
+    }
+
+    #[test]
+    fn parses_undo_with_default_count() {
+        let cli = Cli::parse_from(["ambrogio", "undo"]);
+        assert!(matches!(cli.command, Some(Command::Undo { count: 1 })));
+
+        let cli = Cli::parse_from(["ambrogio", "u", "3"]);
+        assert!(matches!(cli.command, Some(Command::Undo { count: 3 })));
+    }
+
     #[test]
     fn parses_projects_list() {
         let cli = Cli::parse_from(["ambrogio", "projects", "list"]);
@@ -172,24 +861,104 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parses_projects_rename() {
+        let cli = Cli::parse_from(["ambrogio", "p", "rename", "Work", "Office"]);
+        match cli.command {
+            Some(Command::Projects {
+                action: ProjectAction::Rename { old, new },
+            }) => {
+                assert_eq!(old, "Work");
+                assert_eq!(new, "Office");
+            }
+            _ => panic!("expected Projects Rename"),
+        }
+    }
+
+    #[test]
+    fn parses_projects_reorder() {
+        let cli = Cli::parse_from(["ambrogio", "projects", "reorder", "Home", "1"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Projects {
+                action: ProjectAction::Reorder { name, position: 1 },
+            }) if name == "Home"
+        ));
+    }
+
+    #[test]
+    fn parses_projects_set() {
+        let cli = Cli::parse_from([
+            "ambrogio",
+            "projects",
+            "set",
+            "Work",
+            "--status",
+            "paused",
+            "--pomodoro",
+            "50",
+        ]);
+        match cli.command {
+            Some(Command::Projects {
+                action:
+                    ProjectAction::Set {
+                        name,
+                        description,
+                        status,
+                        colour,
+                        pomodoro,
+                    },
+            }) => {
+                assert_eq!(name, "Work");
+                assert_eq!(description, None);
+                assert_eq!(status.as_deref(), Some("paused"));
+                assert_eq!(colour, None);
+                assert_eq!(pomodoro, Some(50));
+            }
+            _ => panic!("expected Projects Set"),
+        }
+    }
+
+    #[test]
+    fn parses_projects_archive() {
+        let cli = Cli::parse_from(["ambrogio", "projects", "archive", "Side"]);
+        assert!(matches!(
+            cli.command,
+            Some(Command::Projects {
+                action: ProjectAction::Archive { name },
+            }) if name == "Side"
+        ));
+    }
+
     #[test]
     fn parses_pomodoro_start() {
         let cli = Cli::parse_from(["ambrogio", "pomodoro", "start"]);
         assert!(matches!(
             cli.command,
             Some(Command::Pomodoro {
-                action: PomodoroAction::Start
+                action: PomodoroAction::Start { task: None }
             })
         ));
     }
 
+    #[test]
+    fn parses_pomodoro_start_with_task_id() {
+        let cli = Cli::parse_from(["ambrogio", "pomodoro", "start", "--task", "ab12"]);
+        match cli.command {
+            Some(Command::Pomodoro {
+                action: PomodoroAction::Start { task },
+            }) => assert_eq!(task.as_deref(), Some("ab12")),
+            _ => panic!("expected Pomodoro Start"),
+        }
+    }
+
     #[test]
     fn alias_t_l_parses_as_tasks_list() {
         let cli = Cli::parse_from(["ambrogio", "t", "l"]);
         assert!(matches!(
             cli.command,
             Some(Command::Tasks {
-                action: TaskAction::List
+                action: TaskAction::List { .. }
             })
         ));
     }
@@ -199,7 +968,7 @@ mod tests {
         let cli = Cli::parse_from(["ambrogio", "t", "a", "buy milk"]);
         match cli.command {
             Some(Command::Tasks {
-                action: TaskAction::Add { description },
+                action: TaskAction::Add { description, .. },
             }) => assert_eq!(description, "buy milk"),
             _ => panic!("expected Tasks Add via alias"),
         }
@@ -211,7 +980,7 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Command::Tasks {
-                action: TaskAction::Complete
+                action: TaskAction::Complete { id: None }
             })
         ));
     }
@@ -222,7 +991,7 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Command::Tasks {
-                action: TaskAction::Delete
+                action: TaskAction::Delete { id: None }
             })
         ));
     }
@@ -231,7 +1000,7 @@ mod tests {
     fn alias_n_parses_as_note() {
         let cli = Cli::parse_from(["ambrogio", "n", "a note"]);
         match cli.command {
-            Some(Command::Note { text }) => assert_eq!(text, "a note"),
+            Some(Command::Note { text, .. }) => assert_eq!(text, "a note"),
             _ => panic!("expected Note via alias"),
         }
     }
@@ -253,7 +1022,7 @@ mod tests {
         assert!(matches!(
             cli.command,
             Some(Command::Pomodoro {
-                action: PomodoroAction::Start
+                action: PomodoroAction::Start { task: None }
             })
         ));
     }
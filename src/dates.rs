@@ -0,0 +1,356 @@
+use anyhow::Result;
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use std::fmt;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// A relative amount of time such as `3d`, `2w` or `1m`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Span {
+    Days(u32),
+    Weeks(u32),
+    Months(u32),
+}
+
+impl Span {
+    /// Parses `3d`, `3 days`, `2w`, `2 weeks`, `1m`, `1 month` and friends.
+    pub fn parse(input: &str) -> Option<Span> {
+        let input = input.trim();
+        let split = input
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(input.len());
+        let (number, unit) = input.split_at(split);
+        let n: u32 = if number.is_empty() && !unit.is_empty() {
+            1
+        } else {
+            number.parse().ok()?
+        };
+
+        match unit.trim() {
+            "d" | "day" | "days" => Some(Span::Days(n)),
+            "w" | "week" | "weeks" => Some(Span::Weeks(n)),
+            "m" | "month" | "months" => Some(Span::Months(n)),
+            _ => None,
+        }
+    }
+
+    /// The day this span after `date`. Errors past the last supported date.
+    pub fn after(self, date: NaiveDate) -> Result<NaiveDate> {
+        match self {
+            Span::Days(n) => date.checked_add_days(Days::new(n.into())),
+            Span::Weeks(n) => date.checked_add_days(Days::new(u64::from(n) * 7)),
+            Span::Months(n) => date.checked_add_months(Months::new(n)),
+        }
+        .ok_or_else(|| anyhow::anyhow!("Date out of range: {} after {}", self, format_date(date)))
+    }
+
+    /// The day this span before `date`. Errors before the first supported
+    /// date.
+    pub fn before(self, date: NaiveDate) -> Result<NaiveDate> {
+        match self {
+            Span::Days(n) => date.checked_sub_days(Days::new(n.into())),
+            Span::Weeks(n) => date.checked_sub_days(Days::new(u64::from(n) * 7)),
+            Span::Months(n) => date.checked_sub_months(Months::new(n)),
+        }
+        .ok_or_else(|| anyhow::anyhow!("Date out of range: {} before {}", self, format_date(date)))
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Span::Days(1) => write!(f, "day"),
+            Span::Weeks(1) => write!(f, "week"),
+            Span::Months(1) => write!(f, "month"),
+            Span::Days(n) => write!(f, "{}d", n),
+            Span::Weeks(n) => write!(f, "{}w", n),
+            Span::Months(n) => write!(f, "{}m", n),
+        }
+    }
+}
+
+/// A repetition rule such as `week`, `2d` or `month on 1st`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recurrence {
+    pub span: Span,
+    /// Pins monthly rules to a day of the month, clamped to the month's end
+    pub day_of_month: Option<u32>,
+}
+
+fn parse_ordinal(input: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| input.strip_suffix(suffix))?;
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+impl Recurrence {
+    pub fn parse(input: &str) -> Option<Recurrence> {
+        let input = input.trim().to_lowercase();
+        let (span, day) = match input.split_once(" on ") {
+            Some((span, day)) => (span, Some(day)),
+            None => (input.as_str(), None),
+        };
+
+        let span = Span::parse(span)
+            .filter(|s| !matches!(s, Span::Days(0) | Span::Weeks(0) | Span::Months(0)))?;
+        let day_of_month = match day {
+            Some(day) if matches!(span, Span::Months(_)) => Some(parse_ordinal(day.trim())?),
+            Some(_) => return None,
+            None => None,
+        };
+
+        Some(Recurrence { span, day_of_month })
+    }
+
+    /// The occurrence following one on `date`.
+    pub fn next(self, date: NaiveDate) -> Result<NaiveDate> {
+        let next = self.span.after(date)?;
+        Ok(match self.day_of_month {
+            Some(day) => (1..=day)
+                .rev()
+                .find_map(|d| next.with_day(d))
+                .unwrap_or(next),
+            None => next,
+        })
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.span)?;
+        if let Some(day) = self.day_of_month {
+            write!(f, " on {}", ordinal(day))?;
+        }
+        Ok(())
+    }
+}
+
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(DATE_FORMAT).to_string()
+}
+
+pub fn parse_iso_date(input: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(input, DATE_FORMAT).ok()
+}
+
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead == 0 { 7 } else { ahead };
+    today.checked_add_days(Days::new(ahead.into()))
+}
+
+/// Parses a date written as `YYYY-MM-DD` or in natural language relative to
+/// `today`: `today`, `tomorrow`, `yesterday`, weekday names (`friday`,
+/// `next fri`), `next week`, `next month`, `in 3 days` or `2w`.
+pub fn parse_date(input: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let normalized = input
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+
+    if let Some(date) = parse_iso_date(&normalized) {
+        return Ok(date);
+    }
+
+    let date = match normalized.as_str() {
+        "today" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        "yesterday" => today.pred_opt(),
+        "next week" => next_weekday(today, Weekday::Mon),
+        "next month" => today
+            .checked_add_months(Months::new(1))
+            .and_then(|date| date.with_day(1)),
+        other => {
+            let other = other.strip_prefix("next ").unwrap_or(other);
+            match other.parse::<Weekday>() {
+                Ok(weekday) => next_weekday(today, weekday),
+                Err(_) => {
+                    let other = other.strip_prefix("in ").unwrap_or(other);
+                    if let Some(span) = Span::parse(other) {
+                        return span.after(today);
+                    }
+                    None
+                }
+            }
+        }
+    };
+
+    date.ok_or_else(|| anyhow::anyhow!("Cannot understand date '{}'", input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    // 2026-10-17 is a Saturday
+    const TODAY: (i32, u32, u32) = (2026, 10, 17);
+
+    fn parse(input: &str) -> NaiveDate {
+        parse_date(input, date(TODAY.0, TODAY.1, TODAY.2)).unwrap()
+    }
+
+    #[test]
+    fn parses_iso_dates() {
+        assert_eq!(parse("2026-12-01"), date(2026, 12, 1));
+    }
+
+    #[test]
+    fn parses_relative_days() {
+        assert_eq!(parse("today"), date(2026, 10, 17));
+        assert_eq!(parse("Tomorrow"), date(2026, 10, 18));
+        assert_eq!(parse("yesterday"), date(2026, 10, 16));
+    }
+
+    #[test]
+    fn parses_weekdays_as_next_occurrence() {
+        assert_eq!(parse("monday"), date(2026, 10, 19));
+        assert_eq!(parse("next fri"), date(2026, 10, 23));
+        assert_eq!(parse("saturday"), date(2026, 10, 24));
+    }
+
+    #[test]
+    fn parses_next_week_and_month() {
+        assert_eq!(parse("next week"), date(2026, 10, 19));
+        assert_eq!(parse("next month"), date(2026, 11, 1));
+    }
+
+    #[test]
+    fn parses_offsets() {
+        assert_eq!(parse("in 3 days"), date(2026, 10, 20));
+        assert_eq!(parse("2w"), date(2026, 10, 31));
+        assert_eq!(parse("in  1   month"), date(2026, 11, 17));
+    }
+
+    #[test]
+    fn rejects_unknown_input() {
+        let result = parse_date("someday", date(2026, 10, 17));
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Cannot understand"));
+    }
+
+    #[test]
+    fn span_months_clamp_to_end_of_month() {
+        assert_eq!(
+            Span::Months(1).after(date(2026, 1, 31)).unwrap(),
+            date(2026, 2, 28)
+        );
+    }
+
+    #[test]
+    fn far_offsets_are_out_of_range() {
+        let result = parse_date("in 999999999 days", date(2026, 10, 17));
+        assert!(result.unwrap_err().to_string().contains("out of range"));
+        assert!(Span::Days(4_000_000_000).after(date(2026, 10, 17)).is_err());
+
+        let yearly = Recurrence::parse("12m").unwrap();
+        assert!(yearly.next(NaiveDate::MAX).is_err());
+    }
+
+    #[test]
+    fn span_before_goes_back_in_time() {
+        assert_eq!(
+            Span::Days(14).before(date(2026, 10, 17)).unwrap(),
+            date(2026, 10, 3)
+        );
+        assert_eq!(
+            Span::Weeks(1).before(date(2026, 10, 17)).unwrap(),
+            date(2026, 10, 10)
+        );
+        assert_eq!(
+            Span::Months(1).before(date(2026, 3, 31)).unwrap(),
+            date(2026, 2, 28)
+        );
+        let result = Span::Months(99_999_999).before(date(2026, 10, 17));
+        assert!(result.unwrap_err().to_string().contains("out of range"));
+    }
+
+    #[test]
+    fn parses_recurrence_rules() {
+        assert_eq!(
+            Recurrence::parse("week"),
+            Some(Recurrence {
+                span: Span::Weeks(1),
+                day_of_month: None
+            })
+        );
+        assert_eq!(
+            Recurrence::parse("2d"),
+            Some(Recurrence {
+                span: Span::Days(2),
+                day_of_month: None
+            })
+        );
+        assert_eq!(
+            Recurrence::parse("month on 1st"),
+            Some(Recurrence {
+                span: Span::Months(1),
+                day_of_month: Some(1)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_recurrence_rules() {
+        assert_eq!(Recurrence::parse("0d"), None);
+        assert_eq!(Recurrence::parse("week on 1st"), None);
+        assert_eq!(Recurrence::parse("month on 32nd"), None);
+        assert_eq!(Recurrence::parse("fortnight"), None);
+    }
+
+    #[test]
+    fn recurrence_round_trips_through_display() {
+        for rule in [
+            "day",
+            "week",
+            "month",
+            "3d",
+            "2w",
+            "month on 1st",
+            "2m on 22nd",
+        ] {
+            assert_eq!(Recurrence::parse(rule).unwrap().to_string(), rule);
+        }
+    }
+
+    #[test]
+    fn recurrence_next_shifts_by_span() {
+        let weekly = Recurrence::parse("week").unwrap();
+        assert_eq!(weekly.next(date(2026, 10, 17)).unwrap(), date(2026, 10, 24));
+    }
+
+    #[test]
+    fn recurrence_next_pins_day_of_month() {
+        let first = Recurrence::parse("month on 1st").unwrap();
+        assert_eq!(first.next(date(2026, 10, 17)).unwrap(), date(2026, 11, 1));
+
+        let last = Recurrence::parse("month on 31st").unwrap();
+        assert_eq!(last.next(date(2026, 1, 31)).unwrap(), date(2026, 2, 28));
+    }
+
+    #[test]
+    fn span_parse_rejects_garbage() {
+        assert_eq!(Span::parse("3x"), None);
+        assert_eq!(Span::parse(""), None);
+        assert_eq!(Span::parse("week"), Some(Span::Weeks(1)));
+    }
+}
@@ -0,0 +1,1990 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::str::FromStr;
+
+use crate::dates::{format_date, parse_iso_date, Recurrence};
+
+const ID_LENGTH: usize = 4;
+const ID_ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+const SUB_ITEM_INDENT: &str = "  ";
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Whether `project` is the project `name` or one of its sub-projects.
+pub fn is_within(project: &str, name: &str) -> bool {
+    project
+        .strip_prefix(name)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The byte offsets of the `/` separating the parts of a project name;
+/// `\/` is a slash within a title.
+fn separators(name: &str) -> impl Iterator<Item = usize> + '_ {
+    name.match_indices('/')
+        .map(|(i, _)| i)
+        .filter(|&i| !name[..i].ends_with('\\'))
+}
+
+/// The parts of a project name: its parents' titles and its own, still
+/// escaped.
+pub fn name_parts(name: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for i in separators(name) {
+        parts.push(&name[start..i]);
+        start = i + 1;
+    }
+    parts.push(&name[start..]);
+    parts
+}
+
+/// The name of the project `name` is a sub-project of, if any.
+pub fn parent_name(name: &str) -> Option<&str> {
+    separators(name).last().map(|i| &name[..i])
+}
+
+/// The title of a project header written as part of a name.
+fn escape_title(title: &str) -> String {
+    title.replace('/', "\\/")
+}
+
+/// A part of a project name as written in its header.
+pub fn unescape_title(part: &str) -> String {
+    part.replace("\\/", "/")
+}
+
+pub fn is_valid_id(id: &str) -> bool {
+    id.len() == ID_LENGTH && id.bytes().all(|b| ID_ALPHABET.contains(&b))
+}
+
+pub fn generate_id(existing: &HashSet<String>) -> String {
+    loop {
+        let mut value = RandomState::new().build_hasher().finish();
+        let mut id = String::with_capacity(ID_LENGTH);
+        for _ in 0..ID_LENGTH {
+            id.push(ID_ALPHABET[(value % ID_ALPHABET.len() as u64) as usize] as char);
+            value /= ID_ALPHABET.len() as u64;
+        }
+        if !existing.contains(&id) {
+            return id;
+        }
+    }
+}
+
+/// The text of a `- [ ]` (open), `- [x]` (done) or `- [>]` (waiting)
+/// line, and the mark between the brackets.
+fn parse_todo_line(line: &str) -> Option<(String, char)> {
+    let trimmed = line.trim();
+    [' ', 'x', '>'].into_iter().find_map(|mark| {
+        trimmed
+            .strip_prefix(&format!("- [{}] ", mark))
+            .map(|desc| (desc.to_string(), mark))
+    })
+}
+
+/// A `## Name` project header, or a deeper `### Name` one for a
+/// sub-project. Returns the nesting level (0 for `##`) and the name.
+fn parse_project_header(line: &str) -> Option<(usize, String)> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if hashes < 2 {
+        return None;
+    }
+    line[hashes..]
+        .strip_prefix(' ')
+        .map(|name| (hashes - 2, name.trim().to_string()))
+}
+
+/// Task priority, `A` being the most important.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    A,
+    B,
+    C,
+}
+
+impl FromStr for Priority {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "A" => Ok(Priority::A),
+            "B" => Ok(Priority::B),
+            "C" => Ok(Priority::C),
+            _ => anyhow::bail!("Unknown priority '{}' (expected A, B or C)", s),
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let letter = match self {
+            Priority::A => "A",
+            Priority::B => "B",
+            Priority::C => "C",
+        };
+        write!(f, "{}", letter)
+    }
+}
+
+/// Where a project stands. `Archived` projects are hidden from task
+/// listings and pickers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Active,
+    Paused,
+    Done,
+    Archived,
+}
+
+impl FromStr for Status {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "active" => Ok(Status::Active),
+            "paused" => Ok(Status::Paused),
+            "done" => Ok(Status::Done),
+            "archived" => Ok(Status::Archived),
+            _ => anyhow::bail!(
+                "Unknown status '{}' (expected active, paused, done or archived)",
+                s
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Status::Active => "active",
+            Status::Paused => "paused",
+            Status::Done => "done",
+            Status::Archived => "archived",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A terminal colour for a project's heading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colour {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+}
+
+const COLOURS: [(Colour, &str); 6] = [
+    (Colour::Red, "red"),
+    (Colour::Green, "green"),
+    (Colour::Yellow, "yellow"),
+    (Colour::Blue, "blue"),
+    (Colour::Magenta, "magenta"),
+    (Colour::Cyan, "cyan"),
+];
+
+impl FromStr for Colour {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        COLOURS
+            .iter()
+            .find(|(_, name)| name.eq_ignore_ascii_case(s))
+            .map(|(colour, _)| *colour)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown colour '{}' (expected red, green, yellow, blue, magenta or cyan)",
+                    s
+                )
+            })
+    }
+}
+
+impl fmt::Display for Colour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = COLOURS
+            .iter()
+            .find(|(colour, _)| colour == self)
+            .map_or("", |(_, name)| name);
+        write!(f, "{}", name)
+    }
+}
+
+/// Optional details of a project, kept in a `> meta:` line right below its
+/// header: a free-text description followed by `status:`, `colour:` and
+/// `pomodoro:` (minutes) tokens. Other `> ` lines are plain blockquotes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProjectMeta {
+    pub description: String,
+    pub status: Option<Status>,
+    pub colour: Option<Colour>,
+    /// Length of pomodoros on the project's tasks, in minutes
+    pub pomodoro: Option<u32>,
+}
+
+impl ProjectMeta {
+    fn parse(line: &str) -> Option<Self> {
+        let text = line.strip_prefix('>')?.trim_start().strip_prefix("meta:")?;
+        let mut meta = ProjectMeta::default();
+        let mut words = Vec::new();
+        for word in text.split_whitespace() {
+            let applied = match word.split_once(':') {
+                Some(("status", value)) if meta.status.is_none() => {
+                    value.parse().map(|s| meta.status = Some(s)).is_ok()
+                }
+                Some(("colour", value)) if meta.colour.is_none() => {
+                    value.parse().map(|c| meta.colour = Some(c)).is_ok()
+                }
+                Some(("pomodoro", value)) if meta.pomodoro.is_none() => value
+                    .parse()
+                    .ok()
+                    .filter(|minutes| *minutes > 0)
+                    .map(|minutes| meta.pomodoro = Some(minutes))
+                    .is_some(),
+                _ => false,
+            };
+            if !applied {
+                words.push(word);
+            }
+        }
+        meta.description = words.join(" ");
+        Some(meta)
+    }
+
+    fn render(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.description.is_empty() {
+            parts.push(self.description.clone());
+        }
+        if let Some(status) = self.status {
+            parts.push(format!("status:{}", status));
+        }
+        if let Some(colour) = self.colour {
+            parts.push(format!("colour:{}", colour));
+        }
+        if let Some(minutes) = self.pomodoro {
+            parts.push(format!("pomodoro:{}", minutes));
+        }
+        format!("> meta: {}", parts.join(" "))
+    }
+}
+
+/// An indented line below a task.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubItem {
+    Pomodoro {
+        started_at: NaiveDateTime,
+        cancelled: bool,
+        raw: String,
+    },
+    Note {
+        text: String,
+        raw: String,
+    },
+    /// When the task was completed
+    Completed {
+        at: NaiveDateTime,
+        raw: String,
+    },
+    /// When the task was moved to the trash, and the ID of the task it was
+    /// a subtask of
+    Deleted {
+        at: NaiveDateTime,
+        parent: Option<String>,
+        raw: String,
+    },
+    /// A nested `- [ ]` line, owning the lines indented below it
+    Task(Task),
+    Unknown(String),
+}
+
+impl SubItem {
+    fn pomodoro(indent: &str, started_at: NaiveDateTime, cancelled: bool) -> Self {
+        let status = if cancelled { " cancelled" } else { "" };
+        let raw = format!(
+            "{}- 🍅 {}{}",
+            indent,
+            started_at.format(TIMESTAMP_FORMAT),
+            status
+        );
+        SubItem::Pomodoro {
+            started_at,
+            cancelled,
+            raw,
+        }
+    }
+
+    fn note(indent: &str, text: &str) -> Self {
+        SubItem::Note {
+            text: text.to_string(),
+            raw: format!("{}- 📝 {}", indent, text),
+        }
+    }
+
+    fn completed(indent: &str, at: NaiveDateTime) -> Self {
+        SubItem::Completed {
+            at,
+            raw: format!("{}- ✅ {}", indent, at.format(TIMESTAMP_FORMAT)),
+        }
+    }
+
+    fn deleted(indent: &str, at: NaiveDateTime, parent: Option<&str>) -> Self {
+        let mut raw = format!("{}- 🗑 {}", indent, at.format(TIMESTAMP_FORMAT));
+        if let Some(parent) = parent {
+            raw.push_str(&format!(" under:{}", parent));
+        }
+        SubItem::Deleted {
+            at,
+            parent: parent.map(String::from),
+            raw,
+        }
+    }
+
+    fn parse(line: &str) -> Self {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("- 🗑 ") {
+            let (stamp, parent) = match rest.split_once(" under:") {
+                Some((stamp, parent)) if is_valid_id(parent) => (stamp, Some(parent)),
+                _ => (rest, None),
+            };
+            if let Ok(at) = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT) {
+                return SubItem::Deleted {
+                    at,
+                    parent: parent.map(String::from),
+                    raw: line.to_string(),
+                };
+            }
+        } else if let Some(stamp) = trimmed.strip_prefix("- ✅ ") {
+            if let Ok(at) = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT) {
+                return SubItem::Completed {
+                    at,
+                    raw: line.to_string(),
+                };
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("- 🍅 ") {
+            let (stamp, cancelled) = match rest.strip_suffix(" cancelled") {
+                Some(stamp) => (stamp, true),
+                None => (rest, false),
+            };
+            if let Ok(started_at) = NaiveDateTime::parse_from_str(stamp, TIMESTAMP_FORMAT) {
+                return SubItem::Pomodoro {
+                    started_at,
+                    cancelled,
+                    raw: line.to_string(),
+                };
+            }
+        } else if let Some(text) = trimmed.strip_prefix("- 📝 ") {
+            return SubItem::Note {
+                text: text.to_string(),
+                raw: line.to_string(),
+            };
+        }
+        SubItem::Unknown(line.to_string())
+    }
+
+    fn render(&self, lines: &mut Vec<String>) {
+        match self {
+            SubItem::Pomodoro { raw, .. }
+            | SubItem::Note { raw, .. }
+            | SubItem::Completed { raw, .. }
+            | SubItem::Deleted { raw, .. }
+            | SubItem::Unknown(raw) => lines.push(raw.clone()),
+            SubItem::Task(task) => task.render(lines),
+        }
+    }
+}
+
+/// Moves the task with ID `id` in `items` right before its sibling task
+/// `anchor`, or right after it. Returns false unless both are in `items`.
+fn reorder<T>(
+    items: &mut Vec<T>,
+    as_task: fn(&T) -> Option<&Task>,
+    id: &str,
+    anchor: &str,
+    after: bool,
+) -> bool {
+    let find = |items: &[T], id: &str| {
+        items
+            .iter()
+            .position(|item| as_task(item).is_some_and(|t| t.id.as_deref() == Some(id)))
+    };
+    let (Some(from), Some(_)) = (find(items, id), find(items, anchor)) else {
+        return false;
+    };
+    let item = items.remove(from);
+    let to = find(items, anchor).unwrap_or(from) + usize::from(after);
+    items.insert(to, item);
+    true
+}
+
+/// The item directly after the task with ID `id` in `items`, if that is a
+/// task: `Some(None)` when it is not, `None` when `id` is not in `items`.
+fn task_after<'a, T>(
+    items: &'a [T],
+    as_task: fn(&T) -> Option<&Task>,
+    id: &str,
+) -> Option<Option<&'a Task>> {
+    let position = items
+        .iter()
+        .position(|item| as_task(item).is_some_and(|t| t.id.as_deref() == Some(id)))?;
+    Some(items.get(position + 1).and_then(as_task))
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// A `- [ ]` / `- [x]` line together with its indented sub-items, which
+/// may include subtasks.
+///
+/// Inline `key:value` tokens (`due:2026-10-20`, `pri:A`, ...) anywhere in
+/// the line are parsed into fields and removed from `description`. Only a
+/// trailing `id:k3f9` token is the task's ID.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Task {
+    raw: String,
+    /// Number of leading spaces; subtasks sit deeper than their parent
+    indent: usize,
+    pub done: bool,
+    /// Handed off to someone else, `- [>]`
+    pub waiting: bool,
+    pub description: String,
+    pub id: Option<String>,
+    pub priority: Option<Priority>,
+    pub due: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    /// Day the task was snoozed until, from `start:`
+    pub start: Option<NaiveDate>,
+    pub recurrence: Option<Recurrence>,
+    /// Expected number of pomodoros, from `est:4🍅`
+    pub estimate: Option<usize>,
+    /// IDs of the tasks that must be done before this one, from `blocked:`
+    pub blocked_by: Vec<String>,
+    pub sub_items: Vec<SubItem>,
+}
+
+impl Task {
+    /// Creates an open task. Metadata tokens in `text` are parsed like they
+    /// would be when reading the file.
+    pub fn new(text: &str, id: String) -> Self {
+        let mut task = Task {
+            id: Some(id),
+            ..Task::default()
+        };
+        task.set_text(text);
+        task.raw = task.render_line();
+        task
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let (text, mark) = parse_todo_line(line)?;
+        let mut task = Task {
+            raw: line.to_string(),
+            indent: indent_of(line),
+            done: mark == 'x',
+            waiting: mark == '>',
+            ..Task::default()
+        };
+        task.set_text(&text);
+        Some(task)
+    }
+
+    /// `#tag` words of the description, without the `#`.
+    pub fn tags(&self) -> Vec<String> {
+        self.labels('#')
+    }
+
+    /// `@context` words of the description, without the `@`.
+    pub fn contexts(&self) -> Vec<String> {
+        self.labels('@')
+    }
+
+    fn labels(&self, sigil: char) -> Vec<String> {
+        self.description
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix(sigil))
+            .map(|label| label.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+            .filter(|label| {
+                label.starts_with(|c: char| c.is_alphabetic())
+                    && label
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+            })
+            .map(|label| label.to_string())
+            .collect()
+    }
+
+    fn set_text(&mut self, text: &str) {
+        let mut tokens: Vec<&str> = text.split_whitespace().collect();
+        if self.id.is_none() {
+            let id = tokens.last().and_then(|token| token.strip_prefix("id:"));
+            if let Some(id) = id.filter(|id| is_valid_id(id)) {
+                self.id = Some(id.to_string());
+                tokens.pop();
+            }
+        }
+        let mut words = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            match self.apply_recurrence(&tokens[i..]) {
+                0 if self.apply_token(tokens[i]) => i += 1,
+                0 => {
+                    words.push(tokens[i]);
+                    i += 1;
+                }
+                consumed => i += consumed,
+            }
+        }
+        self.description = words.join(" ");
+    }
+
+    /// Stores an `every:` rule starting at `tokens[0]`, which may continue
+    /// over the following words (`every:month on 1st`). Returns the number
+    /// of tokens consumed.
+    fn apply_recurrence(&mut self, tokens: &[&str]) -> usize {
+        let Some(rule) = tokens[0].strip_prefix("every:") else {
+            return 0;
+        };
+        if self.recurrence.is_some() {
+            return 0;
+        }
+
+        if let [_, "on", day, ..] = tokens {
+            if let Some(recurrence) = Recurrence::parse(&format!("{} on {}", rule, day)) {
+                self.recurrence = Some(recurrence);
+                return 3;
+            }
+        }
+        match Recurrence::parse(rule) {
+            Some(recurrence) => {
+                self.recurrence = Some(recurrence);
+                1
+            }
+            None => 0,
+        }
+    }
+
+    /// Stores a recognised metadata token. Returns false for anything else,
+    /// which then stays part of the description.
+    fn apply_token(&mut self, token: &str) -> bool {
+        let Some((key, value)) = token.split_once(':') else {
+            return false;
+        };
+        match key {
+            "pri" if self.priority.is_none() && value.len() == 1 => match value.parse() {
+                Ok(priority) => self.priority = Some(priority),
+                Err(_) => return false,
+            },
+            "due" if self.due.is_none() => match parse_iso_date(value) {
+                Some(date) => self.due = Some(date),
+                None => return false,
+            },
+            "scheduled" if self.scheduled.is_none() => match parse_iso_date(value) {
+                Some(date) => self.scheduled = Some(date),
+                None => return false,
+            },
+            "start" if self.start.is_none() => match parse_iso_date(value) {
+                Some(date) => self.start = Some(date),
+                None => return false,
+            },
+            "est" if self.estimate.is_none() => {
+                match value.strip_suffix('🍅').unwrap_or(value).parse() {
+                    Ok(estimate) if estimate > 0 => self.estimate = Some(estimate),
+                    _ => return false,
+                }
+            }
+            "blocked" if is_valid_id(value) && !self.blocked_by.iter().any(|id| id == value) => {
+                self.blocked_by.push(value.to_string())
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// The description followed by its metadata tokens, except the ID.
+    pub fn text(&self) -> String {
+        let mut text = self.description.clone();
+        if let Some(priority) = self.priority {
+            text.push_str(&format!(" pri:{}", priority));
+        }
+        if let Some(due) = self.due {
+            text.push_str(&format!(" due:{}", format_date(due)));
+        }
+        if let Some(scheduled) = self.scheduled {
+            text.push_str(&format!(" scheduled:{}", format_date(scheduled)));
+        }
+        if let Some(start) = self.start {
+            text.push_str(&format!(" start:{}", format_date(start)));
+        }
+        if let Some(recurrence) = self.recurrence {
+            text.push_str(&format!(" every:{}", recurrence));
+        }
+        if let Some(estimate) = self.estimate {
+            text.push_str(&format!(" est:{}🍅", estimate));
+        }
+        for id in &self.blocked_by {
+            text.push_str(&format!(" blocked:{}", id));
+        }
+        text
+    }
+
+    /// Replaces the description and metadata with those parsed from `text`,
+    /// as returned by `text()`. The ID, state and sub-items are kept.
+    pub fn edit(&mut self, text: &str) {
+        *self = Task {
+            raw: std::mem::take(&mut self.raw),
+            indent: self.indent,
+            done: self.done,
+            waiting: self.waiting,
+            id: self.id.take(),
+            sub_items: std::mem::take(&mut self.sub_items),
+            ..Task::default()
+        };
+        self.set_text(text);
+    }
+
+    fn render_line(&self) -> String {
+        let mark = match (self.done, self.waiting) {
+            (true, _) => 'x',
+            (false, true) => '>',
+            (false, false) => ' ',
+        };
+        let mut line = format!("{}- [{}] {}", " ".repeat(self.indent), mark, self.text());
+        if let Some(id) = &self.id {
+            line.push_str(&format!(" id:{}", id));
+        }
+        line
+    }
+
+    /// The original line if the task is unchanged since parsing, otherwise
+    /// a freshly rendered one.
+    fn line(&self) -> String {
+        match Task::parse(&self.raw) {
+            Some(original) if original.render_line() == self.render_line() => self.raw.clone(),
+            _ => self.render_line(),
+        }
+    }
+
+    fn render(&self, lines: &mut Vec<String>) {
+        lines.push(self.line());
+        for sub_item in &self.sub_items {
+            sub_item.render(lines);
+        }
+    }
+
+    fn child_indent(&self) -> String {
+        " ".repeat(self.indent + SUB_ITEM_INDENT.len())
+    }
+
+    /// Moves the task and everything below it to the given indentation,
+    /// keeping the relative indentation of the lines below it.
+    fn set_indent(&mut self, indent: usize) {
+        let from = self.indent;
+        self.indent = indent;
+        for sub_item in &mut self.sub_items {
+            match sub_item {
+                SubItem::Task(task) => task.set_indent((task.indent + indent).saturating_sub(from)),
+                SubItem::Pomodoro { raw, .. }
+                | SubItem::Note { raw, .. }
+                | SubItem::Completed { raw, .. }
+                | SubItem::Deleted { raw, .. }
+                | SubItem::Unknown(raw) => {
+                    let current = indent_of(raw);
+                    *raw = format!(
+                        "{}{}",
+                        " ".repeat((current + indent).saturating_sub(from)),
+                        &raw[current..]
+                    );
+                }
+            }
+        }
+    }
+
+    /// Attaches an indented line read from the file, handing it down to the
+    /// last subtask when it is indented deeper than that subtask.
+    fn push_line(&mut self, line: &str) {
+        if let Some(SubItem::Task(subtask)) = self.sub_items.last_mut() {
+            if indent_of(line) > subtask.indent {
+                subtask.push_line(line);
+                return;
+            }
+        }
+        match Task::parse(line) {
+            Some(task) => self.sub_items.push(SubItem::Task(task)),
+            None => self.sub_items.push(SubItem::parse(line)),
+        }
+    }
+
+    pub fn add_pomodoro(&mut self, started_at: NaiveDateTime, cancelled: bool) {
+        let indent = self.child_indent();
+        self.sub_items
+            .push(SubItem::pomodoro(&indent, started_at, cancelled));
+    }
+
+    pub fn add_note(&mut self, text: &str) {
+        let indent = self.child_indent();
+        self.sub_items.push(SubItem::note(&indent, text));
+    }
+
+    /// Marks the task done, recording when with a `✅` sub-item.
+    pub fn complete(&mut self, at: NaiveDateTime) {
+        let indent = self.child_indent();
+        self.done = true;
+        self.waiting = false;
+        self.sub_items.push(SubItem::completed(&indent, at));
+    }
+
+    /// Marks the task open again, dropping its completion time.
+    pub fn reopen(&mut self) {
+        self.done = false;
+        self.sub_items
+            .retain(|s| !matches!(s, SubItem::Completed { .. }));
+    }
+
+    /// When the task was last completed, if that was recorded.
+    pub fn completed_at(&self) -> Option<NaiveDateTime> {
+        self.sub_items
+            .iter()
+            .filter_map(|s| match s {
+                SubItem::Completed { at, .. } => Some(*at),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Records that the task was moved to the trash, from under `parent`
+    /// if it was a subtask, with a `🗑` sub-item.
+    pub fn trash(&mut self, at: NaiveDateTime, parent: Option<&str>) {
+        let indent = self.child_indent();
+        self.sub_items.push(SubItem::deleted(&indent, at, parent));
+    }
+
+    /// Drops the `🗑` sub-item of a task taken out of the trash. Returns the
+    /// ID of the task it was a subtask of.
+    pub fn untrash(&mut self) -> Option<String> {
+        let parent = self.sub_items.iter().find_map(|s| match s {
+            SubItem::Deleted { parent, .. } => parent.clone(),
+            _ => None,
+        });
+        self.sub_items
+            .retain(|s| !matches!(s, SubItem::Deleted { .. }));
+        parent
+    }
+
+    /// When the task was moved to the trash.
+    pub fn deleted_at(&self) -> Option<NaiveDateTime> {
+        self.sub_items
+            .iter()
+            .filter_map(|s| match s {
+                SubItem::Deleted { at, .. } => Some(*at),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Gives the task and its subtasks fresh IDs where they are missing or
+    /// already in `taken`, and adds them all to `taken`.
+    pub fn claim_ids(&mut self, taken: &mut HashSet<String>) {
+        self.for_each_mut(&mut |task| {
+            let id = match task.id.take() {
+                Some(id) if !taken.contains(&id) => id,
+                _ => generate_id(taken),
+            };
+            taken.insert(id.clone());
+            task.id = Some(id);
+        });
+    }
+
+    /// For done tasks, the best known completion time: the recorded one, or
+    /// for tasks completed before that was recorded, their latest 🍅.
+    pub fn finished_at(&self) -> Option<NaiveDateTime> {
+        if !self.done {
+            return None;
+        }
+        self.completed_at().or_else(|| {
+            self.sub_items
+                .iter()
+                .filter_map(|s| match s {
+                    SubItem::Pomodoro { started_at, .. } => Some(*started_at),
+                    _ => None,
+                })
+                .max()
+        })
+    }
+
+    /// The number of completed and of cancelled 🍅 sub-items.
+    pub fn pomodoros(&self) -> (usize, usize) {
+        self.sub_items
+            .iter()
+            .fold((0, 0), |(completed, cancelled), s| match s {
+                SubItem::Pomodoro {
+                    cancelled: true, ..
+                } => (completed, cancelled + 1),
+                SubItem::Pomodoro { .. } => (completed + 1, cancelled),
+                _ => (completed, cancelled),
+            })
+    }
+
+    /// The text of its 📝 notes, in file order.
+    pub fn notes(&self) -> Vec<String> {
+        self.sub_items
+            .iter()
+            .filter_map(|s| match s {
+                SubItem::Note { text, .. } => Some(text.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn add_subtask(&mut self, mut task: Task) {
+        task.set_indent(self.indent + SUB_ITEM_INDENT.len());
+        self.sub_items.push(SubItem::Task(task));
+    }
+
+    /// Direct subtasks, in file order.
+    pub fn subtasks(&self) -> impl Iterator<Item = &Task> {
+        self.sub_items.iter().filter_map(|s| match s {
+            SubItem::Task(task) => Some(task),
+            _ => None,
+        })
+    }
+
+    fn subtasks_mut(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.sub_items.iter_mut().filter_map(|s| match s {
+            SubItem::Task(task) => Some(task),
+            _ => None,
+        })
+    }
+
+    /// The task and all its subtasks, depth first.
+    pub fn descendants(&self) -> Vec<&Task> {
+        let mut tasks = vec![self];
+        for subtask in self.subtasks() {
+            tasks.extend(subtask.descendants());
+        }
+        tasks
+    }
+
+    fn for_each_mut(&mut self, f: &mut impl FnMut(&mut Task)) {
+        f(self);
+        for subtask in self.subtasks_mut() {
+            subtask.for_each_mut(f);
+        }
+    }
+
+    fn find_mut(&mut self, id: &str) -> Option<&mut Task> {
+        if self.id.as_deref() == Some(id) {
+            return Some(self);
+        }
+        self.subtasks_mut().find_map(|t| t.find_mut(id))
+    }
+
+    /// Inserts `task` as a sibling right after the subtask with ID `after`,
+    /// searching the whole tree. Hands `task` back if there is no such subtask.
+    fn insert_after(&mut self, after: &str, mut task: Task) -> Option<Task> {
+        let position = self
+            .sub_items
+            .iter()
+            .position(|s| matches!(s, SubItem::Task(t) if t.id.as_deref() == Some(after)));
+        if let Some(position) = position {
+            task.set_indent(self.indent + SUB_ITEM_INDENT.len());
+            self.sub_items.insert(position + 1, SubItem::Task(task));
+            return None;
+        }
+        for subtask in self.subtasks_mut() {
+            match subtask.insert_after(after, task) {
+                Some(unplaced) => task = unplaced,
+                None => return None,
+            }
+        }
+        Some(task)
+    }
+
+    /// See `Document::task_after`.
+    fn subtask_after(&self, id: &str) -> Option<Option<&Task>> {
+        let as_task: fn(&SubItem) -> Option<&Task> = |s| match s {
+            SubItem::Task(task) => Some(task),
+            _ => None,
+        };
+        task_after(&self.sub_items, as_task, id)
+            .or_else(|| self.subtasks().find_map(|t| t.subtask_after(id)))
+    }
+
+    /// See `Document::reorder_task`.
+    fn reorder_subtask(&mut self, id: &str, anchor: &str, after: bool) -> bool {
+        let as_task: fn(&SubItem) -> Option<&Task> = |s| match s {
+            SubItem::Task(task) => Some(task),
+            _ => None,
+        };
+        reorder(&mut self.sub_items, as_task, id, anchor, after)
+            || self
+                .subtasks_mut()
+                .any(|t| t.reorder_subtask(id, anchor, after))
+    }
+
+    fn remove_subtask(&mut self, id: &str) -> Option<Task> {
+        let position = self
+            .sub_items
+            .iter()
+            .position(|s| matches!(s, SubItem::Task(t) if t.id.as_deref() == Some(id)));
+        if let Some(position) = position {
+            if let SubItem::Task(task) = self.sub_items.remove(position) {
+                return Some(task);
+            }
+        }
+        self.subtasks_mut().find_map(|t| t.remove_subtask(id))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    Task(Task),
+    /// Any line inside a project section that is not a task, kept verbatim.
+    Text(String),
+}
+
+/// A `## Name` section, or a `### Name` sub-project section below one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Project {
+    header: String,
+    /// The text of its header, e.g. `Backend` or `CI/CD`
+    title: String,
+    /// The titles of the enclosing projects and its own, joined by `/`,
+    /// e.g. `Work/Backend`. A `/` within a title is escaped as `\/`.
+    pub name: String,
+    /// 0 for `##` projects, 1 for `###` sub-projects, and so on
+    level: usize,
+    /// The `> ` line holding `meta`, as read from the file
+    meta_line: Option<String>,
+    pub meta: ProjectMeta,
+    pub entries: Vec<Entry>,
+}
+
+impl Project {
+    /// Creates an empty project; `Work/Backend` is a sub-project of `Work`
+    /// while `CI\/CD` is a project titled `CI/CD`.
+    pub fn new(name: &str) -> Self {
+        let parts = name_parts(name);
+        let level = parts.len() - 1;
+        let title = unescape_title(parts[level]);
+        Project {
+            header: format!("{} {}", "#".repeat(level + 2), title),
+            title,
+            name: name.to_string(),
+            level,
+            meta_line: None,
+            meta: ProjectMeta::default(),
+            entries: Vec::new(),
+        }
+    }
+
+    /// The name of the enclosing project, if this is a sub-project.
+    pub fn parent(&self) -> Option<&str> {
+        parent_name(&self.name)
+    }
+
+    /// Changes its name, and its title to the last part of `name`.
+    pub fn rename(&mut self, name: &str) {
+        let parts = name_parts(name);
+        self.title = unescape_title(parts[parts.len() - 1]);
+        self.name = name.to_string();
+    }
+
+    /// Whether this is the project `name` or one of its sub-projects.
+    pub fn is_within(&self, name: &str) -> bool {
+        is_within(&self.name, name)
+    }
+
+    pub fn tasks(&self) -> impl Iterator<Item = &Task> {
+        self.entries.iter().filter_map(|e| match e {
+            Entry::Task(task) => Some(task),
+            Entry::Text(_) => None,
+        })
+    }
+
+    pub fn tasks_mut(&mut self) -> impl Iterator<Item = &mut Task> {
+        self.entries.iter_mut().filter_map(|e| match e {
+            Entry::Task(task) => Some(task),
+            Entry::Text(_) => None,
+        })
+    }
+
+    /// Appends a task after the last non-blank line of the section, so blank
+    /// separator lines stay between sections. Subtasks become top-level tasks.
+    pub fn push_task(&mut self, mut task: Task) {
+        task.set_indent(0);
+        let position = self.content_end();
+        self.entries.insert(position, Entry::Task(task));
+    }
+
+    /// Index of the first of the blank lines ending the section.
+    fn content_end(&self) -> usize {
+        self.entries
+            .iter()
+            .rposition(|e| !matches!(e, Entry::Text(text) if text.trim().is_empty()))
+            .map_or(0, |i| i + 1)
+    }
+
+    /// Removes the top-level tasks matching `predicate`, with everything
+    /// below them.
+    pub fn take_tasks(&mut self, mut predicate: impl FnMut(&Task) -> bool) -> Vec<Task> {
+        let mut taken = Vec::new();
+        let mut kept = Vec::new();
+        for entry in self.entries.drain(..) {
+            match entry {
+                Entry::Task(task) if predicate(&task) => taken.push(task),
+                entry => kept.push(entry),
+            }
+        }
+        self.entries = kept;
+        taken
+    }
+
+    fn header_line(&self) -> String {
+        match parse_project_header(&self.header) {
+            Some((level, title)) if level == self.level && title == self.title => {
+                self.header.clone()
+            }
+            _ => format!("{} {}", "#".repeat(self.level + 2), self.title),
+        }
+    }
+
+    /// The original metadata line if unchanged, a fresh one if changed, and
+    /// none without metadata.
+    fn meta_line(&self) -> Option<String> {
+        match &self.meta_line {
+            Some(raw) if ProjectMeta::parse(raw).as_ref() == Some(&self.meta) => Some(raw.clone()),
+            _ if self.meta == ProjectMeta::default() => None,
+            _ => Some(self.meta.render()),
+        }
+    }
+}
+
+/// A parsed `todos.md`. Rendering an unmodified document reproduces the
+/// input byte for byte; only touched lines are re-rendered.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Document {
+    /// Lines before the first project header, kept verbatim.
+    preamble: Vec<String>,
+    pub projects: Vec<Project>,
+    trailing_newline: bool,
+}
+
+impl Document {
+    pub fn parse(content: &str) -> Self {
+        let trailing_newline = content.ends_with('\n');
+        let body = content.strip_suffix('\n').unwrap_or(content);
+
+        let mut doc = Document {
+            trailing_newline,
+            ..Document::default()
+        };
+        if content.is_empty() {
+            return doc;
+        }
+
+        for line in body.split('\n') {
+            if let Some((level, title)) = parse_project_header(line) {
+                let name = match doc.projects.iter().rev().find(|p| p.level < level) {
+                    Some(parent) => format!("{}/{}", parent.name, escape_title(&title)),
+                    None => escape_title(&title),
+                };
+                doc.projects.push(Project {
+                    header: line.to_string(),
+                    title,
+                    name,
+                    level,
+                    meta_line: None,
+                    meta: ProjectMeta::default(),
+                    entries: Vec::new(),
+                });
+                continue;
+            }
+
+            let Some(project) = doc.projects.last_mut() else {
+                doc.preamble.push(line.to_string());
+                continue;
+            };
+
+            if project.entries.is_empty() && project.meta_line.is_none() {
+                if let Some(meta) = ProjectMeta::parse(line) {
+                    project.meta_line = Some(line.to_string());
+                    project.meta = meta;
+                    continue;
+                }
+            }
+
+            if line.starts_with(SUB_ITEM_INDENT) {
+                if let Some(Entry::Task(task)) = project.entries.last_mut() {
+                    task.push_line(line);
+                    continue;
+                }
+            }
+
+            match Task::parse(line) {
+                Some(task) => project.entries.push(Entry::Task(task)),
+                None => project.entries.push(Entry::Text(line.to_string())),
+            }
+        }
+
+        doc
+    }
+
+    pub fn project(&self, name: &str) -> Option<&Project> {
+        self.projects.iter().find(|p| p.name == name)
+    }
+
+    pub fn project_mut(&mut self, name: &str) -> Option<&mut Project> {
+        self.projects.iter_mut().find(|p| p.name == name)
+    }
+
+    /// Adds a project at the end of the document, or a sub-project at the
+    /// end of its parent's sub-projects. Blank lines ending the section
+    /// before a sub-project move below it, so separators stay in place.
+    pub fn push_project(&mut self, mut project: Project) {
+        let before = project
+            .parent()
+            .and_then(|parent| self.projects.iter().rposition(|p| p.is_within(parent)));
+        let position = match before {
+            Some(i) => {
+                let blank = self.projects[i].content_end();
+                project
+                    .entries
+                    .extend(self.projects[i].entries.split_off(blank));
+                i + 1
+            }
+            None => self.projects.len(),
+        };
+        self.projects.insert(position, project);
+        self.trailing_newline = true;
+    }
+
+    /// The project with the given name, added first if it is missing,
+    /// together with any missing parent projects.
+    pub fn project_or_insert(&mut self, name: &str) -> &mut Project {
+        if self.project(name).is_none() {
+            let project = Project::new(name);
+            if let Some(parent) = project.parent() {
+                self.project_or_insert(parent);
+            }
+            self.push_project(project);
+        }
+        self.project_mut(name).expect("project was just inserted")
+    }
+
+    pub fn remove_project(&mut self, name: &str) -> Option<Project> {
+        let index = self.projects.iter().position(|p| p.name == name)?;
+        Some(self.projects.remove(index))
+    }
+
+    /// Every task including subtasks in file order, paired with its project.
+    pub fn tasks(&self) -> impl Iterator<Item = (&Project, &Task)> {
+        self.projects
+            .iter()
+            .flat_map(|p| p.tasks().flat_map(|t| t.descendants()).map(move |t| (p, t)))
+    }
+
+    pub fn task_mut(&mut self, id: &str) -> Option<&mut Task> {
+        self.projects
+            .iter_mut()
+            .flat_map(|p| p.tasks_mut())
+            .find_map(|t| t.find_mut(id))
+    }
+
+    /// Inserts `task` directly after the block of the task with ID `after`,
+    /// at the same nesting level. Does nothing if there is no such task.
+    pub fn insert_after(&mut self, after: &str, mut task: Task) {
+        for project in &mut self.projects {
+            let position = project
+                .entries
+                .iter()
+                .position(|e| matches!(e, Entry::Task(t) if t.id.as_deref() == Some(after)));
+            if let Some(position) = position {
+                project.entries.insert(position + 1, Entry::Task(task));
+                return;
+            }
+            for parent in project.tasks_mut() {
+                match parent.insert_after(after, task) {
+                    Some(unplaced) => task = unplaced,
+                    None => return,
+                }
+            }
+        }
+    }
+
+    /// The task directly after the block of the task `id`, where
+    /// `insert_after` puts one.
+    pub fn task_after(&self, id: &str) -> Option<&Task> {
+        let as_task: fn(&Entry) -> Option<&Task> = |e| match e {
+            Entry::Task(task) => Some(task),
+            Entry::Text(_) => None,
+        };
+        self.projects
+            .iter()
+            .find_map(|project| {
+                task_after(&project.entries, as_task, id)
+                    .or_else(|| project.tasks().find_map(|t| t.subtask_after(id)))
+            })
+            .flatten()
+    }
+
+    /// Removes a task or subtask together with everything below it.
+    pub fn remove_task(&mut self, id: &str) -> Option<Task> {
+        for project in &mut self.projects {
+            let position = project
+                .entries
+                .iter()
+                .position(|e| matches!(e, Entry::Task(t) if t.id.as_deref() == Some(id)));
+            if let Some(position) = position {
+                if let Entry::Task(task) = project.entries.remove(position) {
+                    return Some(task);
+                }
+            }
+            if let Some(task) = project.tasks_mut().find_map(|t| t.remove_subtask(id)) {
+                return Some(task);
+            }
+        }
+        None
+    }
+
+    /// Moves a task or subtask with everything below it right before its
+    /// sibling `anchor`, or right after it (with everything below that).
+    /// Returns false if there are no such sibling tasks.
+    pub fn reorder_task(&mut self, id: &str, anchor: &str, after: bool) -> bool {
+        let as_task: fn(&Entry) -> Option<&Task> = |e| match e {
+            Entry::Task(task) => Some(task),
+            Entry::Text(_) => None,
+        };
+        self.projects.iter_mut().any(|project| {
+            reorder(&mut project.entries, as_task, id, anchor, after)
+                || project
+                    .tasks_mut()
+                    .any(|t| t.reorder_subtask(id, anchor, after))
+        })
+    }
+
+    /// Moves a project with its sub-projects to `position` (0-based, or
+    /// last if past the end) among the projects sharing its parent. Blank
+    /// lines separating the sections stay where they were. Returns false
+    /// if there is no such project.
+    pub fn reorder_project(&mut self, name: &str, position: usize) -> bool {
+        let parent = parent_name(name);
+        let starts: Vec<usize> = (0..self.projects.len())
+            .filter(|&i| self.projects[i].parent() == parent)
+            .filter(|&i| parent.is_none_or(|p| self.projects[i].is_within(p)))
+            .collect();
+        let Some(from) = starts.iter().position(|&i| self.projects[i].name == name) else {
+            return false;
+        };
+        let ranges: Vec<(usize, usize)> = starts
+            .iter()
+            .map(|&start| {
+                let end = (start + 1..self.projects.len())
+                    .find(|&i| !self.projects[i].is_within(&self.projects[start].name))
+                    .unwrap_or(self.projects.len());
+                (start, end)
+            })
+            .collect();
+
+        let first = ranges[0].0;
+        let last = ranges[ranges.len() - 1].1;
+        let suffix = self.projects.split_off(last);
+        let mut region = self.projects.split_off(first);
+        let mut blocks = Vec::new();
+        let mut gaps = Vec::new();
+        for &(start, end) in ranges.iter().rev() {
+            let mut block = region.split_off(start - first);
+            let last = &mut block[end - start - 1];
+            let blank = last.content_end();
+            gaps.push(last.entries.split_off(blank));
+            blocks.push(block);
+        }
+        blocks.reverse();
+        gaps.reverse();
+
+        let block = blocks.remove(from);
+        blocks.insert(position.min(blocks.len()), block);
+        for (mut block, gap) in blocks.into_iter().zip(gaps) {
+            if let Some(project) = block.last_mut() {
+                project.entries.extend(gap);
+            }
+            self.projects.extend(block);
+        }
+        self.projects.extend(suffix);
+        true
+    }
+
+    pub fn ids(&self) -> HashSet<String> {
+        self.tasks().filter_map(|(_, t)| t.id.clone()).collect()
+    }
+
+    /// Gives every task without an ID, or reusing the ID of a task above it,
+    /// a fresh one, not in the document nor in `taken`, and adds all IDs to
+    /// `taken`. Returns whether any task was changed.
+    pub fn assign_missing_ids(&mut self, taken: &mut HashSet<String>) -> bool {
+        taken.extend(self.ids());
+        let mut seen = HashSet::new();
+        let mut changed = false;
+        for task in self.projects.iter_mut().flat_map(|p| p.tasks_mut()) {
+            task.for_each_mut(&mut |task| {
+                if !task.id.as_ref().is_some_and(|id| seen.insert(id.clone())) {
+                    let id = generate_id(taken);
+                    seen.insert(id.clone());
+                    taken.insert(id.clone());
+                    task.id = Some(id);
+                    changed = true;
+                }
+            });
+        }
+        changed
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines: Vec<String> = self.preamble.clone();
+        for project in &self.projects {
+            lines.push(project.header_line());
+            lines.extend(project.meta_line());
+            for entry in &project.entries {
+                match entry {
+                    Entry::Task(task) => task.render(&mut lines),
+                    Entry::Text(text) => lines.push(text.clone()),
+                }
+            }
+        }
+
+        write!(f, "{}", lines.join("\n"))?;
+        if self.trailing_newline && !lines.is_empty() {
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_open_todo_line() {
+        let (desc, mark) = parse_todo_line("- [ ] buy milk").unwrap();
+        assert_eq!(desc, "buy milk");
+        assert_eq!(mark, ' ');
+    }
+
+    #[test]
+    fn parses_done_todo_line() {
+        let (desc, mark) = parse_todo_line("- [x] buy milk").unwrap();
+        assert_eq!(desc, "buy milk");
+        assert_eq!(mark, 'x');
+    }
+
+    #[test]
+    fn parses_waiting_todo_line() {
+        let (desc, mark) = parse_todo_line("  - [>] review PR @alice").unwrap();
+        assert_eq!(desc, "review PR @alice");
+        assert_eq!(mark, '>');
+
+        let mut task = Task::parse("- [>] review PR @alice id:ab12").unwrap();
+        assert!(task.waiting && !task.done);
+        task.complete(NaiveDateTime::default());
+        assert_eq!(task.line(), "- [x] review PR @alice id:ab12");
+        task.reopen();
+        assert_eq!(task.line(), "- [ ] review PR @alice id:ab12");
+    }
+
+    #[test]
+    fn ignores_non_todo_lines() {
+        assert!(parse_todo_line("# heading").is_none());
+        assert!(parse_todo_line("## Project").is_none());
+        assert!(parse_todo_line("some text").is_none());
+        assert!(parse_todo_line("").is_none());
+    }
+
+    #[test]
+    fn parses_project_header_line() {
+        assert_eq!(
+            parse_project_header("## Work"),
+            Some((0, "Work".to_string()))
+        );
+        assert_eq!(
+            parse_project_header("## My Project"),
+            Some((0, "My Project".to_string()))
+        );
+        assert_eq!(
+            parse_project_header("### Backend"),
+            Some((1, "Backend".to_string()))
+        );
+    }
+
+    #[test]
+    fn parses_sub_projects_with_full_names() {
+        let content = "## Work\n- [ ] plan id:aaaa\n### Backend\n- [ ] api id:bbbb\n#### DB\n### Frontend\n## Home\n";
+        let doc = Document::parse(content);
+
+        let names: Vec<&str> = doc.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Work",
+                "Work/Backend",
+                "Work/Backend/DB",
+                "Work/Frontend",
+                "Home"
+            ]
+        );
+        assert_eq!(doc.to_string(), content);
+        assert!(doc.projects[2].is_within("Work"));
+        assert!(!doc.projects[4].is_within("Work"));
+        assert!(!is_within("Workshop", "Work"));
+    }
+
+    #[test]
+    fn slashes_in_project_titles_are_escaped_in_names() {
+        let content = "## CI/CD\n### Deploy\n";
+        let mut doc = Document::parse(content);
+
+        let names: Vec<&str> = doc.projects.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["CI\\/CD", "CI\\/CD/Deploy"]);
+        assert_eq!(doc.projects[0].parent(), None);
+        assert_eq!(doc.projects[1].parent(), Some("CI\\/CD"));
+        assert_eq!(name_parts("CI\\/CD/Deploy"), vec!["CI\\/CD", "Deploy"]);
+
+        doc.push_project(Project::new("Ops\\/Infra"));
+        assert_eq!(doc.to_string(), "## CI/CD\n### Deploy\n## Ops/Infra\n");
+    }
+
+    #[test]
+    fn parses_project_meta_below_the_header() {
+        let content = "## Work\n>  meta: Day job  status:paused colour:blue pomodoro:50\n- [ ] plan\n## Home\n> meta: Chores\n";
+        let mut doc = Document::parse(content);
+
+        assert_eq!(
+            doc.projects[0].meta,
+            ProjectMeta {
+                description: "Day job".to_string(),
+                status: Some(Status::Paused),
+                colour: Some(Colour::Blue),
+                pomodoro: Some(50),
+            }
+        );
+        assert_eq!(doc.to_string(), content);
+
+        doc.projects[0].meta.status = Some(Status::Archived);
+        doc.projects[1].meta = ProjectMeta::default();
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n> meta: Day job status:archived colour:blue pomodoro:50\n- [ ] plan\n## Home\n"
+        );
+
+        doc.projects[1].meta.pomodoro = Some(15);
+        assert!(doc.to_string().ends_with("## Home\n> meta: pomodoro:15\n"));
+    }
+
+    #[test]
+    fn plain_blockquotes_are_not_project_meta() {
+        let content = "## A\n> quoted status:archived thing\n- [ ] task id:aaaa\n";
+        let mut doc = Document::parse(content);
+
+        assert_eq!(doc.projects[0].meta, ProjectMeta::default());
+        assert_eq!(doc.projects[0].tasks().count(), 1);
+
+        doc.projects[0].meta.colour = Some(Colour::Red);
+        assert_eq!(
+            doc.to_string(),
+            "## A\n> meta: colour:red\n> quoted status:archived thing\n- [ ] task id:aaaa\n"
+        );
+    }
+
+    #[test]
+    fn project_meta_keeps_unknown_tokens_in_the_description() {
+        let meta = ProjectMeta::parse("> meta: see status:later pomodoro:0").unwrap();
+        assert_eq!(meta.description, "see status:later pomodoro:0");
+        assert_eq!(meta.status, None);
+        assert_eq!(meta.pomodoro, None);
+    }
+
+    #[test]
+    fn reorder_task_moves_next_to_a_sibling() {
+        let content = "## Work\n- [ ] a id:aaaa\n- [x] b id:bbbb\n- [ ] c id:cccc\n  - 📝 note\n- [ ] d id:dddd\n\n## Home\n- [ ] e id:eeee\n";
+        let mut doc = Document::parse(content);
+
+        assert!(doc.reorder_task("dddd", "cccc", false));
+        assert!(doc.to_string().starts_with(
+            "## Work\n- [ ] a id:aaaa\n- [x] b id:bbbb\n- [ ] d id:dddd\n- [ ] c id:cccc\n  - 📝 note\n\n"
+        ));
+
+        assert!(doc.reorder_task("aaaa", "cccc", true));
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [x] b id:bbbb\n- [ ] d id:dddd\n- [ ] c id:cccc\n  - 📝 note\n- [ ] a id:aaaa\n\n## Home\n- [ ] e id:eeee\n"
+        );
+        assert!(!doc.reorder_task("zzzz", "aaaa", false));
+        assert!(!doc.reorder_task("aaaa", "eeee", false));
+    }
+
+    #[test]
+    fn reorder_task_moves_subtasks_within_their_parent() {
+        let mut doc = Document::parse(
+            "## Work\n- [ ] parent id:aaaa\n  - 🍅 2026-10-17 10:00\n  - [ ] one id:bbbb\n  - [ ] two id:cccc\n- [ ] other id:dddd\n",
+        );
+
+        assert!(doc.reorder_task("cccc", "bbbb", false));
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ] parent id:aaaa\n  - 🍅 2026-10-17 10:00\n  - [ ] two id:cccc\n  - [ ] one id:bbbb\n- [ ] other id:dddd\n"
+        );
+    }
+
+    #[test]
+    fn reorder_project_moves_sub_projects_and_keeps_separators() {
+        let mut doc = Document::parse(
+            "# Todos\n## Work\n- [ ] a\n### Backend\n- [ ] b\n\n## Home\n- [ ] c\n\n## Side\n- [ ] d\n",
+        );
+
+        assert!(doc.reorder_project("Side", 0));
+        assert_eq!(
+            doc.to_string(),
+            "# Todos\n## Side\n- [ ] d\n\n## Work\n- [ ] a\n### Backend\n- [ ] b\n\n## Home\n- [ ] c\n"
+        );
+
+        assert!(doc.reorder_project("Work", usize::MAX));
+        assert_eq!(
+            doc.to_string(),
+            "# Todos\n## Side\n- [ ] d\n\n## Home\n- [ ] c\n\n## Work\n- [ ] a\n### Backend\n- [ ] b\n"
+        );
+        assert!(!doc.reorder_project("Nowhere", 0));
+    }
+
+    #[test]
+    fn reorder_project_stays_within_the_parent() {
+        let mut doc =
+            Document::parse("## Work\n### Backend\n#### DB\n### Frontend\n## Home\n### Garden\n");
+
+        assert!(doc.reorder_project("Work/Frontend", 0));
+        assert!(doc.reorder_project("Home/Garden", 0));
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n### Frontend\n### Backend\n#### DB\n## Home\n### Garden\n"
+        );
+    }
+
+    #[test]
+    fn project_or_insert_adds_sub_projects_below_their_parent() {
+        let mut doc = Document::parse("## Work\n- [ ] plan\n### Backend\n## Home\n");
+
+        doc.project_or_insert("Work/Frontend");
+        doc.project_or_insert("Office/Ops");
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ] plan\n### Backend\n### Frontend\n## Home\n## Office\n### Ops\n"
+        );
+    }
+
+    #[test]
+    fn ignores_non_header_lines() {
+        assert!(parse_project_header("# heading").is_none());
+        assert!(parse_project_header("- [ ] task").is_none());
+        assert!(parse_project_header("").is_none());
+    }
+
+    #[test]
+    fn parse_ignores_malformed_ids() {
+        let task = Task::parse("- [ ] task id:toolong").unwrap();
+        assert_eq!(task.description, "task id:toolong");
+        assert!(task.id.is_none());
+
+        let task = Task::parse("- [ ] task id:AB12").unwrap();
+        assert_eq!(task.description, "task id:AB12");
+        assert!(task.id.is_none());
+
+        let task = Task::parse("- [ ] task id:ab12").unwrap();
+        assert_eq!(task.description, "task");
+        assert_eq!(task.id.as_deref(), Some("ab12"));
+
+        // Only the trailing token is the ID
+        let task = Task::parse("- [ ] check user id:1234 in logs").unwrap();
+        assert_eq!(task.description, "check user id:1234 in logs");
+        assert!(task.id.is_none());
+    }
+
+    #[test]
+    fn parses_dates_anywhere_in_line() {
+        let task =
+            Task::parse("- [ ] due:2026-10-20 release scheduled:2026-10-18 notes id:ab12").unwrap();
+        assert_eq!(task.description, "release notes");
+        assert_eq!(task.due, NaiveDate::from_ymd_opt(2026, 10, 20));
+        assert_eq!(task.scheduled, NaiveDate::from_ymd_opt(2026, 10, 18));
+    }
+
+    #[test]
+    fn keeps_invalid_dates_in_description() {
+        let task = Task::parse("- [ ] ship due:tomorrow").unwrap();
+        assert_eq!(task.description, "ship due:tomorrow");
+        assert!(task.due.is_none());
+    }
+
+    #[test]
+    fn parses_priority() {
+        let task = Task::parse("- [ ] ship pri:a id:ab12").unwrap();
+        assert_eq!(task.priority, Some(Priority::A));
+        assert_eq!(task.description, "ship");
+
+        let task = Task::parse("- [ ] ship pri:Z").unwrap();
+        assert!(task.priority.is_none());
+        assert_eq!(task.description, "ship pri:Z");
+    }
+
+    #[test]
+    fn extracts_tags_and_contexts_from_description() {
+        let task =
+            Task::parse("- [ ] fix #infra build @office, see #123 and #ci/cd. @ alone id:ab12")
+                .unwrap();
+        assert_eq!(task.tags(), vec!["infra", "ci/cd"]);
+        assert_eq!(task.contexts(), vec!["office"]);
+        assert_eq!(
+            task.description,
+            "fix #infra build @office, see #123 and #ci/cd. @ alone"
+        );
+    }
+
+    #[test]
+    fn parses_recurrence_rules() {
+        let task = Task::parse("- [ ] review deps every:week id:ab12").unwrap();
+        assert_eq!(task.recurrence, Recurrence::parse("week"));
+        assert_eq!(task.description, "review deps");
+
+        let task = Task::parse("- [ ] pay rent every:month on 1st due:2026-11-01").unwrap();
+        assert_eq!(task.recurrence, Recurrence::parse("month on 1st"));
+        assert_eq!(task.description, "pay rent");
+
+        let task = Task::parse("- [ ] stretch every:2d on the balcony").unwrap();
+        assert_eq!(task.recurrence, Recurrence::parse("2d"));
+        assert_eq!(task.description, "stretch on the balcony");
+    }
+
+    #[test]
+    fn keeps_unknown_recurrence_in_description() {
+        let task = Task::parse("- [ ] water plants every:sometimes").unwrap();
+        assert!(task.recurrence.is_none());
+        assert_eq!(task.description, "water plants every:sometimes");
+    }
+
+    #[test]
+    fn renders_recurrence_with_ordinal() {
+        let task = Task::new("pay rent every:month on 1st", "ab12".to_string());
+        assert_eq!(task.line(), "- [ ] pay rent every:month on 1st id:ab12");
+    }
+
+    #[test]
+    fn parses_estimates_and_counts_pomodoros() {
+        let mut task = Task::parse("- [ ] write spec est:4🍅 id:ab12").unwrap();
+        assert_eq!(task.estimate, Some(4));
+        assert_eq!(task.description, "write spec");
+        assert_eq!(Task::parse("- [ ] x est:2").unwrap().estimate, Some(2));
+        assert_eq!(
+            Task::parse("- [ ] x est:0🍅").unwrap().description,
+            "x est:0🍅"
+        );
+
+        task.add_pomodoro(NaiveDateTime::default(), false);
+        task.add_pomodoro(NaiveDateTime::default(), true);
+        task.add_pomodoro(NaiveDateTime::default(), false);
+        task.add_note("🍅 is not a pomodoro here");
+        assert_eq!(task.pomodoros(), (2, 1));
+
+        task.estimate = Some(5);
+        assert_eq!(task.line(), "- [ ] write spec est:5🍅 id:ab12");
+    }
+
+    #[test]
+    fn parses_start_date() {
+        let task = Task::parse("- [ ] renew passport start:2026-11-02 id:ab12").unwrap();
+        assert_eq!(task.start, NaiveDate::from_ymd_opt(2026, 11, 2));
+        assert_eq!(task.description, "renew passport");
+        assert_eq!(task.text(), "renew passport start:2026-11-02");
+    }
+
+    #[test]
+    fn parses_blocked_by_tokens() {
+        let task =
+            Task::parse("- [ ] deploy blocked:ab12 blocked:cd34 blocked:ab12 blocked:x id:ef56")
+                .unwrap();
+        assert_eq!(task.blocked_by, vec!["ab12", "cd34"]);
+        assert_eq!(task.description, "deploy blocked:ab12 blocked:x");
+
+        let mut task = Task::new("deploy pri:A", "ef56".to_string());
+        task.blocked_by.push("ab12".to_string());
+        assert_eq!(task.line(), "- [ ] deploy pri:A blocked:ab12 id:ef56");
+    }
+
+    #[test]
+    fn priority_orders_a_first() {
+        assert!(Priority::A < Priority::B);
+        assert!(Priority::B < Priority::C);
+        assert!("d".parse::<Priority>().is_err());
+    }
+
+    #[test]
+    fn renders_dates_before_id() {
+        let mut task = Task::new("ship due:2026-10-20", "ab12".to_string());
+        task.scheduled = NaiveDate::from_ymd_opt(2026, 10, 18);
+        task.priority = Some(Priority::B);
+        assert_eq!(
+            task.line(),
+            "- [ ] ship pri:B due:2026-10-20 scheduled:2026-10-18 id:ab12"
+        );
+    }
+
+    #[test]
+    fn edit_replaces_text_and_keeps_the_rest() {
+        let mut doc = Document::parse(
+            "## Work\n- [x] tpyo pri:A due:2026-10-20 id:aaaa\n  - 🍅 2026-10-17 10:00\n",
+        );
+        let task = doc.task_mut("aaaa").unwrap();
+        assert_eq!(task.text(), "tpyo pri:A due:2026-10-20");
+
+        task.edit("typo fixed due:2026-10-21");
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [x] typo fixed due:2026-10-21 id:aaaa\n  - 🍅 2026-10-17 10:00\n"
+        );
+    }
+
+    #[test]
+    fn complete_and_reopen_manage_completion_stamp() {
+        let at = NaiveDateTime::parse_from_str("2026-10-17 15:42", TIMESTAMP_FORMAT).unwrap();
+        let mut doc = Document::parse("## Work\n- [ ] task id:aaaa\n  - 📝 n\n");
+
+        doc.task_mut("aaaa").unwrap().complete(at);
+        let content = doc.to_string();
+        assert_eq!(
+            content,
+            "## Work\n- [x] task id:aaaa\n  - 📝 n\n  - ✅ 2026-10-17 15:42\n"
+        );
+
+        let mut doc = Document::parse(&content);
+        let task = doc.task_mut("aaaa").unwrap();
+        assert_eq!(task.completed_at(), Some(at));
+        task.reopen();
+        assert_eq!(doc.to_string(), "## Work\n- [ ] task id:aaaa\n  - 📝 n\n");
+    }
+
+    #[test]
+    fn trash_and_untrash_manage_deletion_stamp() {
+        let at = NaiveDateTime::parse_from_str("2026-10-17 15:42", TIMESTAMP_FORMAT).unwrap();
+        let mut doc = Document::parse("## Work\n- [ ] task id:aaaa\n  - [ ] sub id:bbbb\n");
+
+        doc.task_mut("bbbb").unwrap().trash(at, Some("aaaa"));
+        let content = doc.to_string();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] task id:aaaa\n  - [ ] sub id:bbbb\n    - 🗑 2026-10-17 15:42 under:aaaa\n"
+        );
+
+        let mut doc = Document::parse(&content);
+        let task = doc.task_mut("bbbb").unwrap();
+        assert_eq!(task.deleted_at(), Some(at));
+        assert_eq!(task.untrash().as_deref(), Some("aaaa"));
+        assert_eq!(task.deleted_at(), None);
+    }
+
+    #[test]
+    fn claim_ids_replaces_taken_and_missing_ids() {
+        let mut doc =
+            Document::parse("## Work\n- [ ] task id:aaaa\n  - [ ] sub\n  - [ ] other id:cccc\n");
+        let mut taken: HashSet<String> = ["aaaa".to_string()].into();
+
+        let task = doc.task_mut("aaaa").unwrap();
+        task.claim_ids(&mut taken);
+
+        let ids: Vec<String> = task
+            .descendants()
+            .iter()
+            .filter_map(|t| t.id.clone())
+            .collect();
+        assert_eq!(ids.len(), 3);
+        assert_ne!(ids[0], "aaaa");
+        assert_eq!(ids[2], "cccc");
+        assert_eq!(taken.len(), 4);
+    }
+
+    #[test]
+    fn round_trips_untouched_content_byte_for_byte() {
+        let content = "# My todos\n\nintro text\n## Work   \n- [ ]  spaced  id:ab12\n  - 🍅 2026-02-12 10:00\n  - 🍅 garbage\n  - 📝 note\n    deeper line\n\nfree text\n- [x] done\n\n## Personal\r\n- [ ] crlf\r\n";
+        let doc = Document::parse(content);
+        assert_eq!(doc.to_string(), content);
+    }
+
+    #[test]
+    fn round_trips_missing_trailing_newline() {
+        let content = "## Work\n- [ ] task";
+        assert_eq!(Document::parse(content).to_string(), content);
+        assert_eq!(Document::parse("").to_string(), "");
+    }
+
+    #[test]
+    fn parses_structure() {
+        let doc = Document::parse(
+            "preamble\n## Work\n- [ ] task id:ab12\n  - 🍅 2026-02-12 10:00 cancelled\n  - 📝 a note\n  - other\ntext\n",
+        );
+
+        assert_eq!(doc.preamble, vec!["preamble"]);
+        assert_eq!(doc.projects.len(), 1);
+        let project = &doc.projects[0];
+        assert_eq!(project.name, "Work");
+        assert_eq!(project.entries.len(), 2);
+        assert_eq!(project.entries[1], Entry::Text("text".to_string()));
+
+        let task = project.tasks().next().unwrap();
+        assert_eq!(task.description, "task");
+        assert_eq!(task.id.as_deref(), Some("ab12"));
+        assert!(matches!(
+            task.sub_items[0],
+            SubItem::Pomodoro {
+                cancelled: true,
+                ..
+            }
+        ));
+        assert!(matches!(&task.sub_items[1], SubItem::Note { text, .. } if text == "a note"));
+        assert_eq!(task.sub_items[2], SubItem::Unknown("  - other".to_string()));
+    }
+
+    #[test]
+    fn orphan_tasks_stay_in_preamble() {
+        let doc = Document::parse("- [ ] orphan\n## Work\n");
+        assert_eq!(doc.tasks().count(), 0);
+        assert_eq!(doc.to_string(), "- [ ] orphan\n## Work\n");
+    }
+
+    #[test]
+    fn rerenders_only_modified_tasks() {
+        let mut doc = Document::parse("## Work\n- [ ]  first  id:aaaa\n- [ ]  second  id:bbbb\n");
+
+        doc.task_mut("bbbb").unwrap().done = true;
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ]  first  id:aaaa\n- [x] second id:bbbb\n"
+        );
+    }
+
+    #[test]
+    fn push_task_goes_before_trailing_blank_lines() {
+        let mut doc = Document::parse("## Work\n- [ ] a id:aaaa\n\n## Personal\n");
+
+        doc.project_mut("Work")
+            .unwrap()
+            .push_task(Task::new("b", "bbbb".to_string()));
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ] a id:aaaa\n- [ ] b id:bbbb\n\n## Personal\n"
+        );
+    }
+
+    #[test]
+    fn remove_task_takes_sub_items() {
+        let mut doc = Document::parse("## Work\n- [ ] a id:aaaa\n  - 📝 note\n- [ ] b id:bbbb\n");
+
+        let removed = doc.remove_task("aaaa").unwrap();
+
+        assert_eq!(removed.sub_items.len(), 1);
+        assert_eq!(doc.to_string(), "## Work\n- [ ] b id:bbbb\n");
+    }
+
+    #[test]
+    fn parses_nested_subtasks() {
+        let content = "## Work\n- [ ] parent id:aaaa\n  - 📝 plan\n  - [x] first id:bbbb\n    - 🍅 2026-10-17 10:00\n    - [ ] deep id:cccc\n  - [ ] second id:dddd\n  - 📝 after\n";
+        let doc = Document::parse(content);
+
+        let parent = doc.projects[0].tasks().next().unwrap();
+        let subtasks: Vec<&Task> = parent.subtasks().collect();
+        assert_eq!(subtasks.len(), 2);
+        assert!(subtasks[0].done);
+        assert!(matches!(subtasks[0].sub_items[0], SubItem::Pomodoro { .. }));
+        assert_eq!(subtasks[0].subtasks().next().unwrap().description, "deep");
+        assert!(matches!(&parent.sub_items[3], SubItem::Note { text, .. } if text == "after"));
+
+        let ids: Vec<&str> = doc.tasks().filter_map(|(_, t)| t.id.as_deref()).collect();
+        assert_eq!(ids, vec!["aaaa", "bbbb", "cccc", "dddd"]);
+        assert_eq!(doc.to_string(), content);
+    }
+
+    #[test]
+    fn subtasks_render_and_collect_sub_items_at_their_depth() {
+        let mut doc = Document::parse("## Work\n- [ ] parent id:aaaa\n");
+
+        doc.task_mut("aaaa")
+            .unwrap()
+            .add_subtask(Task::new("child", "bbbb".to_string()));
+        doc.task_mut("bbbb").unwrap().add_note("hi");
+        doc.insert_after("bbbb", Task::new("sibling", "cccc".to_string()));
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ] parent id:aaaa\n  - [ ] child id:bbbb\n    - 📝 hi\n  - [ ] sibling id:cccc\n"
+        );
+    }
+
+    #[test]
+    fn push_project_keeps_blank_lines_between_sections() {
+        let mut doc = Document::parse("## Work\n- [ ] a id:aaaa\n\n## Personal\n");
+
+        doc.push_project(Project::new("Work/Backend"));
+        doc.project_mut("Work/Backend")
+            .unwrap()
+            .push_task(Task::new("api", "bbbb".to_string()));
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ] a id:aaaa\n### Backend\n- [ ] api id:bbbb\n\n## Personal\n"
+        );
+    }
+
+    #[test]
+    fn push_task_outdents_former_subtasks() {
+        let mut doc = Document::parse(
+            "## Work\n- [ ] parent id:aaaa\n  - [ ] child id:bbbb\n    - 📝 n\n## Home\n",
+        );
+
+        let child = doc.remove_task("bbbb").unwrap();
+        doc.project_mut("Home").unwrap().push_task(child);
+
+        assert_eq!(
+            doc.to_string(),
+            "## Work\n- [ ] parent id:aaaa\n## Home\n- [ ] child id:bbbb\n  - 📝 n\n"
+        );
+    }
+
+    #[test]
+    fn remove_task_finds_subtasks() {
+        let mut doc =
+            Document::parse("## Work\n- [ ] parent id:aaaa\n  - [ ] child id:bbbb\n    - 📝 n\n");
+
+        let removed = doc.remove_task("bbbb").unwrap();
+
+        assert_eq!(removed.sub_items.len(), 1);
+        assert_eq!(doc.to_string(), "## Work\n- [ ] parent id:aaaa\n");
+    }
+
+    #[test]
+    fn assign_missing_ids_fills_gaps() {
+        let mut doc = Document::parse("## Work\n- [ ] a\n  - [ ] sub\n- [ ] b id:bbbb\n");
+        let mut taken = HashSet::from(["cccc".to_string()]);
+
+        assert!(doc.assign_missing_ids(&mut taken));
+        assert!(!doc.assign_missing_ids(&mut taken));
+        assert!(doc
+            .tasks()
+            .all(|(_, t)| t.id.as_deref().is_some_and(is_valid_id)));
+        assert_eq!(taken.len(), 4);
+    }
+}
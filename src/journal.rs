@@ -0,0 +1,226 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+fn lines(content: &str) -> Vec<String> {
+    content.split_inclusive('\n').map(String::from).collect()
+}
+
+/// A block of lines of a file replaced by another: `before` starting at line
+/// `start` became `after`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    pub file: String,
+    start: usize,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+impl Change {
+    /// The smallest block of lines turning `original` into `updated`, or
+    /// `None` if they are equal. Pure insertions and deletions keep one
+    /// surrounding line, so reverting them can check where they happened.
+    pub fn between(file: &str, original: &str, updated: &str) -> Option<Change> {
+        if original == updated {
+            return None;
+        }
+
+        let old = lines(original);
+        let new = lines(updated);
+        let mut prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let mut suffix = old
+            .iter()
+            .rev()
+            .zip(new.iter().rev())
+            .take(old.len().min(new.len()) - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        if prefix == old.len() - suffix || prefix == new.len() - suffix {
+            if prefix > 0 {
+                prefix -= 1;
+            } else {
+                suffix = suffix.saturating_sub(1);
+            }
+        }
+
+        Some(Change {
+            file: file.to_string(),
+            start: prefix,
+            before: old[prefix..old.len() - suffix].to_vec(),
+            after: new[prefix..new.len() - suffix].to_vec(),
+        })
+    }
+
+    /// Undoes this change in `content`. The changed block is expected where
+    /// it was written, or else exactly once anywhere in the file, in case
+    /// lines were added or removed around it since. Returns `None` if it
+    /// cannot be found.
+    pub fn revert(&self, content: &str) -> Option<String> {
+        let mut lines = lines(content);
+        let len = self.after.len();
+        let matches = |start: usize| lines.get(start..start + len) == Some(&self.after[..]);
+
+        let start = if matches(self.start) {
+            self.start
+        } else {
+            let mut found = (0..lines.len()).filter(|&start| matches(start));
+            match (found.next(), found.next()) {
+                (Some(start), None) if len > 0 => start,
+                _ => return None,
+            }
+        };
+
+        lines.splice(start..start + len, self.before.iter().cloned());
+        Some(lines.concat())
+    }
+}
+
+/// A recorded operation and the changes it made to each file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub at: String,
+    pub op: String,
+    /// How many earlier operations this one undid
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub undoes: usize,
+    pub changes: Vec<Change>,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// An append-only log of operations, one JSON entry per line.
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn append(&self, entry: &Entry) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    }
+
+    fn entries(&self) -> Result<Vec<Entry>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        fs::read_to_string(&self.path)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    anyhow::anyhow!(
+                        "Invalid entry on line {} of '{}': {}",
+                        i + 1,
+                        self.path.display(),
+                        e
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// The operations that have not been undone yet, oldest first.
+    pub fn undoable(&self) -> Result<Vec<Entry>> {
+        let mut stack = Vec::new();
+        for entry in self.entries()? {
+            if entry.undoes > 0 {
+                stack.truncate(stack.len().saturating_sub(entry.undoes));
+            } else {
+                stack.push(entry);
+            }
+        }
+        Ok(stack)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn round_trip(original: &str, updated: &str) -> Change {
+        let change = Change::between("todos.md", original, updated).unwrap();
+        assert_eq!(change.revert(updated).as_deref(), Some(original));
+        change
+    }
+
+    #[test]
+    fn change_covers_only_the_modified_block() {
+        let change = round_trip("a\nb\nc\nd\n", "a\nB\nC\nd\n");
+        assert_eq!(change.start, 1);
+        assert_eq!(change.before, vec!["b\n", "c\n"]);
+        assert_eq!(change.after, vec!["B\n", "C\n"]);
+    }
+
+    #[test]
+    fn insertions_and_deletions_keep_a_line_of_context() {
+        let change = round_trip("a\nc\n", "a\nb\nc\n");
+        assert_eq!(change.before, vec!["a\n"]);
+        assert_eq!(change.after, vec!["a\n", "b\n"]);
+
+        let change = round_trip("a\nb\n", "b\n");
+        assert_eq!(change.before, vec!["a\n", "b\n"]);
+        assert_eq!(change.after, vec!["b\n"]);
+
+        round_trip("", "a\n");
+        round_trip("a", "a\nb\n");
+        assert_eq!(Change::between("todos.md", "a\n", "a\n"), None);
+    }
+
+    #[test]
+    fn revert_finds_a_block_that_moved() {
+        let change = Change::between("todos.md", "a\nb\nc\n", "a\nB\nc\n").unwrap();
+        assert_eq!(
+            change.revert("new\na\nB\nc\n").as_deref(),
+            Some("new\na\nb\nc\n")
+        );
+    }
+
+    #[test]
+    fn revert_refuses_when_the_block_is_gone_or_ambiguous() {
+        let change = Change::between("todos.md", "a\nb\n", "a\nB\n").unwrap();
+        assert_eq!(change.revert("a\nb\n"), None);
+        assert_eq!(change.revert("x\na\nB\na\nB\n"), None);
+    }
+
+    #[test]
+    fn undone_entries_are_not_undoable() {
+        let dir = TempDir::new().unwrap();
+        let journal = Journal::new(dir.path().join("journal.jsonl"));
+        let entry = |op: &str, undoes| Entry {
+            at: "2026-10-17 15:42".to_string(),
+            op: op.to_string(),
+            undoes,
+            changes: Vec::new(),
+        };
+
+        for e in [entry("add", 0), entry("complete", 0), entry("delete", 0)] {
+            journal.append(&e).unwrap();
+        }
+        journal.append(&entry("undo", 2)).unwrap();
+        journal.append(&entry("add_note", 0)).unwrap();
+
+        let ops: Vec<String> = journal
+            .undoable()
+            .unwrap()
+            .into_iter()
+            .map(|e| e.op)
+            .collect();
+        assert_eq!(ops, vec!["add", "add_note"]);
+    }
+}
@@ -1,12 +1,16 @@
 mod chat;
 mod cli;
 mod config;
+mod dates;
+mod document;
 mod hooks;
+mod journal;
 mod llm;
 mod pomodoro;
+mod storage;
 mod todo;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Local;
 use clap::Parser;
 use rustyline::error::ReadlineError;
@@ -15,10 +19,12 @@ use std::fs;
 use std::io::{self, Write};
 
 use chat::ChatManager;
-use cli::{Cli, Command, PomodoroAction, ProjectAction, TaskAction};
+use cli::{Cli, Command, PomodoroAction, ProjectAction, TagAction, TaskAction, TrashAction};
 use config::{Config, FileConfig};
+use dates::{Recurrence, Span};
+use document::{is_within, name_parts, Colour, Priority, Status};
 use llm::LlmClient;
-use todo::TodoStore;
+use todo::{DateFilter, Filter, NewTodo, Target, TodoStore};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -29,7 +35,11 @@ async fn main() -> Result<()> {
         Some(Command::Tasks { action }) => run_tasks(action),
         Some(Command::Projects { action }) => run_projects(action),
         Some(Command::Pomodoro { action }) => run_pomodoro(action).await,
-        Some(Command::Note { text }) => run_note(&text),
+        Some(Command::Tags { action }) => run_tags(action),
+        Some(Command::Trash { action }) => run_trash(action),
+        Some(Command::Note { text, task }) => run_note(&text, task),
+        Some(Command::Search { query }) => run_search(&query.join(" ")),
+        Some(Command::Undo { count }) => run_undo(count),
     }
 }
 
@@ -53,6 +63,15 @@ fn prompt_selection(prompt: &str, items: &[&str]) -> Result<usize> {
     }
 }
 
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} (y/N): ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
 async fn run_repl() -> Result<()> {
     let config = Config::from_env()?;
 
@@ -122,12 +141,26 @@ fn run_projects(action: ProjectAction) -> Result<()> {
 
     match action {
         ProjectAction::List => {
-            let projects = store.projects()?;
+            let projects = store.project_details()?;
             if projects.is_empty() {
                 println!("No projects.");
             } else {
-                for (i, project) in projects.iter().enumerate() {
-                    println!("  {}. {}", i + 1, project);
+                for (i, (project, meta)) in projects.iter().enumerate() {
+                    let indent = "  ".repeat(name_parts(project).len() - 1);
+                    let mut details = String::new();
+                    if let Some(status) = meta.status {
+                        details.push_str(&format!(" [{}]", status));
+                    }
+                    if let Some(colour) = meta.colour {
+                        details.push_str(&format!(" [{}]", colour));
+                    }
+                    if let Some(minutes) = meta.pomodoro {
+                        details.push_str(&format!(" [{}-min pomodoros]", minutes));
+                    }
+                    if !meta.description.is_empty() {
+                        details.push_str(&format!(" — {}", meta.description));
+                    }
+                    println!("  {}. {}{}{}", i + 1, indent, project, details);
                 }
             }
         }
@@ -135,6 +168,55 @@ fn run_projects(action: ProjectAction) -> Result<()> {
             store.add_project(&name)?;
             println!("Added project: {}", name);
         }
+        ProjectAction::Rename { old, new } => {
+            store.rename_project(&old, &new)?;
+            println!("Renamed project: {} → {}", old, new);
+        }
+        ProjectAction::Reorder { name, position } => {
+            if position == 0 {
+                anyhow::bail!("Positions start at 1");
+            }
+            store.reorder_project(&name, position - 1)?;
+            println!("Moved project {} to position {}", name, position);
+        }
+        ProjectAction::Set {
+            name,
+            description,
+            status,
+            colour,
+            pomodoro,
+        } => {
+            let status = match status {
+                Some(s) if s.eq_ignore_ascii_case("none") => Some(None),
+                Some(s) => Some(Some(s.parse::<Status>()?)),
+                None => None,
+            };
+            let colour = match colour {
+                Some(c) if c.eq_ignore_ascii_case("none") => Some(None),
+                Some(c) => Some(Some(c.parse::<Colour>()?)),
+                None => None,
+            };
+
+            store.update_project(&name, |meta| {
+                if let Some(description) = description {
+                    meta.description = description.split_whitespace().collect::<Vec<_>>().join(" ");
+                }
+                if let Some(status) = status {
+                    meta.status = status;
+                }
+                if let Some(colour) = colour {
+                    meta.colour = colour;
+                }
+                if let Some(minutes) = pomodoro {
+                    meta.pomodoro = Some(minutes).filter(|&m| m > 0);
+                }
+            })?;
+            println!("Updated project: {}", name);
+        }
+        ProjectAction::Archive { name } => {
+            store.update_project(&name, |meta| meta.status = Some(Status::Archived))?;
+            println!("Archived project: {}", name);
+        }
         ProjectAction::Delete => {
             let projects = store.projects()?;
             if projects.is_empty() {
@@ -145,17 +227,11 @@ fn run_projects(action: ProjectAction) -> Result<()> {
             let items: Vec<&str> = projects.iter().map(|p| p.as_str()).collect();
             let selection = prompt_selection("Select a project to delete:", &items)?;
 
-            print!(
-                "Delete '{}' and all its todos? (y/N): ",
+            if confirm(&format!(
+                "Delete '{}' and move all its todos to the trash?",
                 projects[selection]
-            );
-            io::stdout().flush()?;
-
-            let mut confirm = String::new();
-            io::stdin().read_line(&mut confirm)?;
-
-            if confirm.trim().eq_ignore_ascii_case("y") {
-                store.delete_project(&projects[selection])?;
+            ))? {
+                store.delete_project(&projects[selection], Local::now().naive_local())?;
                 println!("Deleted project: {}", projects[selection]);
             } else {
                 println!("Cancelled.");
@@ -171,7 +247,36 @@ fn run_tasks(action: TaskAction) -> Result<()> {
     let store = TodoStore::new(file_config.todos_path);
 
     match action {
-        TaskAction::Add { description } => {
+        TaskAction::Add {
+            description,
+            due,
+            scheduled,
+            priority,
+            every,
+            parent,
+            estimate,
+            blocked_by,
+        } => {
+            let today = Local::now().date_naive();
+            let todo = NewTodo {
+                description,
+                priority: priority.map(|p| p.parse::<Priority>()).transpose()?,
+                due: due.map(|d| dates::parse_date(&d, today)).transpose()?,
+                scheduled: scheduled
+                    .map(|d| dates::parse_date(&d, today))
+                    .transpose()?,
+                recurrence: every.map(|e| parse_recurrence(&e)).transpose()?,
+                estimate: estimate.filter(|&e| e > 0),
+                blocked_by,
+            };
+
+            if let Some(parent) = parent {
+                let parent = store.find(&parent)?;
+                let id = store.add_subtask(&parent.id, &todo)?;
+                println!("Added under {}: {}", parent.description, store.find(&id)?);
+                return Ok(());
+            }
+
             let projects = store.projects()?;
             if projects.is_empty() {
                 println!("No projects. Add a project first with: ambrogio projects add <name>");
@@ -181,75 +286,521 @@ fn run_tasks(action: TaskAction) -> Result<()> {
             let items: Vec<&str> = projects.iter().map(|p| p.as_str()).collect();
             let selection = prompt_selection("Select a project:", &items)?;
 
-            store.add(&projects[selection], &description)?;
-            println!("Added to {}: {}", projects[selection], description);
+            let id = store.add_todo(&projects[selection], &todo)?;
+            println!("Added to {}: {}", projects[selection], store.find(&id)?);
         }
-        TaskAction::List => {
-            store.print_open_todos()?;
+        TaskAction::List {
+            done,
+            since,
+            overdue,
+            today,
+            week,
+            tag,
+            context,
+            project,
+            snoozed,
+        } => {
+            let date = if overdue {
+                Some(DateFilter::Overdue)
+            } else if today {
+                Some(DateFilter::Today)
+            } else if week {
+                Some(DateFilter::Week)
+            } else {
+                None
+            };
+            let today = Local::now().date_naive();
+            let filter = Filter {
+                date,
+                since: since.map(|d| dates::parse_date(&d, today)).transpose()?,
+                tags: tag,
+                contexts: context,
+                project,
+            };
+            if done {
+                store.print_done_todos(&filter, today)?;
+            } else if snoozed {
+                store.print_snoozed_todos(&filter, today)?;
+            } else {
+                store.print_open_todos(&filter, today)?;
+            }
         }
-        TaskAction::Complete => {
-            let open = store.open_todos()?;
-            if open.is_empty() {
+        TaskAction::Complete { id } => {
+            let Some(mut todo) = choose_task(&store, id, "Select a task to complete:")? else {
                 println!("No open tasks to complete.");
                 return Ok(());
+            };
+
+            loop {
+                let completion = store.complete(&todo.id, Local::now().naive_local())?;
+                println!("Completed: {}", todo.description);
+                if let Some(next) = completion.next {
+                    println!("Next occurrence: {}", next);
+                }
+                for unblocked in &completion.unblocked {
+                    println!("Unblocked: {}", unblocked);
+                }
+
+                // Finishing the last subtask offers to finish the parent too
+                match completion.parent {
+                    Some(parent)
+                        if confirm(&format!(
+                            "All subtasks of '{}' are done. Complete it too?",
+                            parent.description
+                        ))? =>
+                    {
+                        todo = parent
+                    }
+                    _ => break,
+                }
+            }
+        }
+        TaskAction::Estimates { project } => {
+            let todos: Vec<todo::Todo> = store
+                .estimated_todos()?
+                .into_iter()
+                .filter(|t| project.as_deref().is_none_or(|p| is_within(&t.project, p)))
+                .collect();
+            todo::print_estimates(&todos);
+        }
+        TaskAction::Waiting => store.print_waiting_todos()?,
+        TaskAction::Wait { id } => {
+            let todo = match id {
+                Some(id) => store.find(&id)?,
+                None => match pick_listed(store.listed_todos()?, "Select a task to hand off:")? {
+                    Some(todo) => todo,
+                    None => {
+                        println!("No open tasks to hand off.");
+                        return Ok(());
+                    }
+                },
+            };
+
+            store.set_waiting(&todo.id, true)?;
+            println!("Waiting: {}", todo.description);
+        }
+        TaskAction::Resume { id } => {
+            let todo = match id {
+                Some(id) => store.find(&id)?,
+                None => match pick_todo(store.waiting_todos()?, "Select a task to take back:")? {
+                    Some(todo) => todo,
+                    None => {
+                        println!("No tasks waiting on others.");
+                        return Ok(());
+                    }
+                },
+            };
+
+            store.set_waiting(&todo.id, false)?;
+            println!("Resumed: {}", todo.description);
+        }
+        TaskAction::Snooze { id, until } => {
+            let today = Local::now().date_naive();
+            let until = if until.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                let date = dates::parse_date(&until, today)?;
+                if date <= today {
+                    anyhow::bail!(
+                        "Cannot snooze until {}: pick a day after today, or 'none' to wake a task up",
+                        dates::format_date(date)
+                    );
+                }
+                Some(date)
+            };
+
+            let todo = match (id, until) {
+                (Some(id), _) => store.find(&id)?,
+                (None, Some(_)) => {
+                    match pick_listed(store.listed_todos()?, "Select a task to snooze:")? {
+                        Some(todo) => todo,
+                        None => {
+                            println!("No open tasks to snooze.");
+                            return Ok(());
+                        }
+                    }
+                }
+                (None, None) => {
+                    match pick_todo(store.snoozed_todos()?, "Select a task to wake up:")? {
+                        Some(todo) => todo,
+                        None => {
+                            println!("No snoozed tasks.");
+                            return Ok(());
+                        }
+                    }
+                }
+            };
+
+            store.snooze(&todo.id, until)?;
+            match until {
+                Some(until) => println!(
+                    "Snoozed until {}: {}",
+                    dates::format_date(until),
+                    todo.description
+                ),
+                None => println!("Woke up: {}", todo.description),
             }
+        }
+        TaskAction::Block { id, by } => {
+            let Some(todo) = choose_task(&store, id, "Select the blocked task:")? else {
+                println!("No open tasks to block.");
+                return Ok(());
+            };
 
-            print_open_todos_for_selection("Select a task to complete:", &open);
-            let selection = read_todo_number(open.len())?;
+            let blocker = store.block(&todo.id, &by)?;
+            println!(
+                "Blocked: {} (waits for {})",
+                todo.description, blocker.description
+            );
+        }
+        TaskAction::Unblock { id, by } => {
+            let todo = match id {
+                Some(id) => store.find(&id)?,
+                None => {
+                    let blocked = store
+                        .listed_todos()?
+                        .into_iter()
+                        .filter(|(_, t)| !t.blocked_by.is_empty())
+                        .collect();
+                    match pick_listed(blocked, "Select a task to unblock:")? {
+                        Some(todo) => todo,
+                        None => {
+                            println!("No blocked tasks.");
+                            return Ok(());
+                        }
+                    }
+                }
+            };
 
-            store.complete(selection)?;
-            println!("Completed: {}", open[selection].description);
+            store.unblock(&todo.id, by.as_deref())?;
+            println!("Unblocked: {}", todo.description);
         }
-        TaskAction::Delete => {
-            let open = store.open_todos()?;
-            if open.is_empty() {
+        TaskAction::Reopen { id } => {
+            let todo = match id {
+                Some(id) => store.find(&id)?,
+                None => match pick_todo(store.done_todos()?, "Select a task to reopen:")? {
+                    Some(todo) => todo,
+                    None => {
+                        println!("No completed tasks to reopen.");
+                        return Ok(());
+                    }
+                },
+            };
+
+            store.reopen(&todo.id)?;
+            println!("Reopened: {}", todo.description);
+        }
+        TaskAction::Delete { id } => {
+            let Some(todo) = choose_task(&store, id, "Select a task to delete:")? else {
                 println!("No open tasks to delete.");
                 return Ok(());
+            };
+
+            store.delete(&todo.id, Local::now().naive_local())?;
+            println!("Moved to the trash: {}", todo.description);
+        }
+        TaskAction::Edit { id, editor } => {
+            let Some(todo) = choose_task(&store, id, "Select a task to edit:")? else {
+                println!("No open tasks to edit.");
+                return Ok(());
+            };
+
+            let text = store.text(&todo.id)?;
+            let edited = if editor {
+                edit_in_editor(&text)?
+            } else {
+                DefaultEditor::new()?.readline_with_initial("Edit: ", (&text, ""))?
+            };
+
+            if edited.trim() == text {
+                println!("Unchanged: {}", todo.description);
+                return Ok(());
             }
 
-            print_open_todos_for_selection("Select a task to delete:", &open);
-            let selection = read_todo_number(open.len())?;
+            store.edit(&todo.id, &edited)?;
+            println!("Edited: {}", store.find(&todo.id)?);
+        }
+        TaskAction::Move { id, to } => {
+            let Some(todo) = choose_task(&store, id, "Select a task to move:")? else {
+                println!("No open tasks to move.");
+                return Ok(());
+            };
+
+            let project = match to {
+                Some(project) => project,
+                None => {
+                    let projects = store.projects()?;
+                    let items: Vec<&str> = projects.iter().map(|p| p.as_str()).collect();
+                    let selection = prompt_selection("Move to which project?", &items)?;
+                    projects[selection].clone()
+                }
+            };
 
-            store.delete(selection)?;
-            println!("Deleted: {}", open[selection].description);
+            store.move_task(&todo.id, &project)?;
+            println!("Moved to {}: {}", project, todo.description);
+        }
+        TaskAction::Reorder { position, id } => {
+            if position == 0 {
+                anyhow::bail!("Positions start at 1");
+            }
+            let Some(todo) = choose_task(&store, id, "Select a task to reorder:")? else {
+                println!("No open tasks to reorder.");
+                return Ok(());
+            };
+
+            let position = store.reorder(&todo.id, position - 1)?;
+            println!("Moved to position {}: {}", position + 1, todo.description);
+        }
+        TaskAction::Top { id } => {
+            let Some(todo) = choose_task(&store, id, "Select a task to move to the top:")? else {
+                println!("No open tasks to reorder.");
+                return Ok(());
+            };
+
+            store.reorder(&todo.id, 0)?;
+            println!("Moved to the top: {}", todo.description);
+        }
+        TaskAction::Bottom { id } => {
+            let Some(todo) = choose_task(&store, id, "Select a task to move to the bottom:")?
+            else {
+                println!("No open tasks to reorder.");
+                return Ok(());
+            };
+
+            store.reorder(&todo.id, usize::MAX)?;
+            println!("Moved to the bottom: {}", todo.description);
+        }
+        TaskAction::Archive { older_than } => {
+            let before = older_than
+                .map(|age| {
+                    Span::parse(&age)
+                        .ok_or_else(|| anyhow::anyhow!("Cannot understand age '{}'", age))?
+                        .before(Local::now().date_naive())
+                })
+                .transpose()?;
+
+            match store.archive(before)? {
+                0 => println!("No completed tasks to archive."),
+                count => println!(
+                    "Archived {} task(s) to {}",
+                    count,
+                    store.archive_path().display()
+                ),
+            }
+        }
+        TaskAction::Prioritize { priority, id } => {
+            let priority = if priority.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(priority.parse::<Priority>()?)
+            };
+
+            let Some(todo) = choose_task(&store, id, "Select a task to prioritize:")? else {
+                println!("No open tasks to prioritize.");
+                return Ok(());
+            };
+
+            store.set_priority(&todo.id, priority)?;
+            match priority {
+                Some(priority) => println!("Set priority {}: {}", priority, todo.description),
+                None => println!("Cleared priority: {}", todo.description),
+            }
         }
     }
 
     Ok(())
 }
 
-fn run_note(text: &str) -> Result<()> {
+/// Lets the user edit `text` in `$EDITOR` (falling back to `vi`) and returns
+/// the result joined into a single line.
+fn edit_in_editor(text: &str) -> Result<String> {
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    // EDITOR may carry arguments, e.g. "code --wait"
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    // Created exclusively and only readable by the user, removed when dropped
+    let mut file = tempfile::Builder::new()
+        .prefix("ambrogio-edit-")
+        .suffix(".md")
+        .tempfile()?;
+    writeln!(file, "{}", text)?;
+    file.flush()?;
+
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(file.path())
+        .status();
+    let edited = fs::read_to_string(file.path());
+
+    if !status
+        .with_context(|| format!("Cannot run editor '{}'", editor))?
+        .success()
+    {
+        anyhow::bail!("Editor '{}' exited with an error", editor);
+    }
+    Ok(edited?.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+fn parse_recurrence(rule: &str) -> Result<Recurrence> {
+    Recurrence::parse(rule)
+        .ok_or_else(|| anyhow::anyhow!("Cannot understand recurrence '{}'", rule))
+}
+
+fn run_tags(action: TagAction) -> Result<()> {
     let file_config = FileConfig::from_env()?;
     let store = TodoStore::new(file_config.todos_path);
-    let open = store.open_todos()?;
 
-    if open.is_empty() {
+    match action {
+        TagAction::List => {
+            let counts = todo::label_counts(&store.open_todos()?);
+            if counts.is_empty() {
+                println!("No tags.");
+            }
+            for (label, count) in counts {
+                println!("  {} ({})", label, count);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_note(text: &str, id: Option<String>) -> Result<()> {
+    let file_config = FileConfig::from_env()?;
+    let store = TodoStore::new(file_config.todos_path);
+
+    let Some(todo) = choose_task(&store, id, "Select a task:")? else {
         println!("No open tasks. Add a task first with: ambrogio tasks add <name>");
         return Ok(());
+    };
+
+    store.add_note(&todo.id, text)?;
+    println!("Added note to: {}", todo.description);
+
+    Ok(())
+}
+
+fn run_trash(action: TrashAction) -> Result<()> {
+    let file_config = FileConfig::from_env()?;
+    let store = TodoStore::new(file_config.todos_path);
+
+    match action {
+        TrashAction::List => {
+            let trashed = store.trashed()?;
+            if trashed.is_empty() {
+                println!("The trash is empty.");
+            } else {
+                todo::print_grouped(&trashed);
+            }
+        }
+        TrashAction::Restore { id, project } => {
+            if let Some(project) = project {
+                let restored = store.restore_project(&project)?;
+                println!("Restored {} task(s) to {}", restored.len(), project);
+                return Ok(());
+            }
+
+            let id = match id {
+                Some(id) => id,
+                None => match pick_todo(store.trashed()?, "Select a task to restore:")? {
+                    Some(todo) => todo.id,
+                    None => {
+                        println!("The trash is empty.");
+                        return Ok(());
+                    }
+                },
+            };
+            let todo = store.restore(&id)?;
+            println!("Restored to {}: {}", todo.project, todo);
+        }
+        TrashAction::Empty => {
+            let count = store.trashed()?.len();
+            if count == 0 {
+                println!("The trash is empty.");
+            } else if confirm(&format!("Permanently delete {} task(s)?", count))? {
+                store.empty_trash()?;
+                println!("Emptied the trash.");
+            } else {
+                println!("Cancelled.");
+            }
+        }
     }
 
-    print_open_todos_for_selection("Select a task:", &open);
-    let selection = read_todo_number(open.len())?;
+    Ok(())
+}
 
-    store.add_note(selection, text)?;
-    println!("Added note to: {}", open[selection].description);
+fn run_search(query: &str) -> Result<()> {
+    let file_config = FileConfig::from_env()?;
+    let store = TodoStore::new(file_config.todos_path);
+
+    let results = store.search(query)?;
+    if results.is_empty() {
+        println!("No matches for '{}'.", query);
+    } else {
+        todo::print_search(&results);
+    }
 
     Ok(())
 }
 
-fn print_open_todos_for_selection(header: &str, todos: &[todo::Todo]) {
-    println!("{}", header);
-    let mut current_project = "";
-    for (i, todo) in todos.iter().enumerate() {
-        if todo.project != current_project {
-            current_project = &todo.project;
-            println!("\n  ## {}", current_project);
-        }
-        println!("  {}. {}", i + 1, todo.description);
+fn run_undo(count: usize) -> Result<()> {
+    let file_config = FileConfig::from_env()?;
+    let store = TodoStore::new(file_config.todos_path);
+
+    let entries = store.undo(count)?;
+    if entries.is_empty() {
+        println!("Nothing to undo.");
+    }
+    for entry in entries {
+        println!("Undone: {} ({})", entry.op, entry.at);
+    }
+
+    Ok(())
+}
+
+/// Looks up the task with the given ID, or prompts for one of the open tasks,
+/// numbered as in `tasks list`, when no ID is given. Returns `None` if there
+/// is nothing to choose from.
+fn choose_task(store: &TodoStore, id: Option<String>, header: &str) -> Result<Option<todo::Todo>> {
+    if let Some(id) = id {
+        return store.find(&id).map(Some);
+    }
+
+    pick_listed(store.listed_todos()?, header)
+}
+
+/// Prompts for one of `todos`, numbered in priority order. Returns `None`
+/// if the list is empty.
+fn pick_todo(todos: Vec<todo::Todo>, header: &str) -> Result<Option<todo::Todo>> {
+    let numbered = todo::sort_by_priority(todos)
+        .into_iter()
+        .enumerate()
+        .map(|(i, todo)| (i + 1, todo))
+        .collect();
+    pick_listed(numbered, header)
+}
+
+/// Prompts for one of `todos` by the number it comes with. Returns `None`
+/// if the list is empty.
+fn pick_listed(todos: Vec<(usize, todo::Todo)>, header: &str) -> Result<Option<todo::Todo>> {
+    if todos.is_empty() {
+        return Ok(None);
     }
+
+    print_open_todos_for_selection(header, &todos);
+    let numbers: Vec<usize> = todos.iter().map(|(number, _)| *number).collect();
+    let selection = read_todo_number(&numbers)?;
+    Ok(todos.into_iter().nth(selection).map(|(_, todo)| todo))
 }
 
-fn read_todo_number(count: usize) -> Result<usize> {
+fn print_open_todos_for_selection(header: &str, todos: &[(usize, todo::Todo)]) {
+    println!("{}", header);
+    todo::print_numbered(todos);
+}
+
+/// Reads one of `numbers` and returns its position.
+fn read_todo_number(numbers: &[usize]) -> Result<usize> {
     loop {
         print!("Enter number: ");
         io::stdout().flush()?;
@@ -258,70 +809,60 @@ fn read_todo_number(count: usize) -> Result<usize> {
         io::stdin().read_line(&mut input)?;
 
         match input.trim().parse::<usize>() {
-            Ok(n) if n >= 1 && n <= count => return Ok(n - 1),
-            _ => println!("Please enter a number between 1 and {}", count),
+            Ok(n) => match numbers.iter().position(|&number| number == n) {
+                Some(position) => return Ok(position),
+                None => println!("Please enter one of the numbers listed above"),
+            },
+            _ => println!("Please enter one of the numbers listed above"),
         }
     }
 }
 
-fn select_task(store: &TodoStore) -> Result<(usize, String)> {
-    let open = store.open_todos()?;
-
-    if open.is_empty() {
-        anyhow::bail!("No open tasks. Add a task first with: ambrogio tasks add <name>");
+fn select_task(store: &TodoStore, id: Option<String>) -> Result<Target> {
+    if let Some(id) = id {
+        return store.target(&id);
     }
 
-    print_open_todos_for_selection("Select a task to focus on:", &open);
-    let selection = read_todo_number(open.len())?;
-    Ok((selection, open[selection].description.clone()))
-}
-
-fn select_or_create_task(store: &TodoStore) -> Result<(usize, String)> {
-    let open = store.open_todos()?;
-    let projects = store.projects()?;
-
-    println!("Select a task to focus on:");
-
-    let mut current_project = "";
-    for (i, todo) in open.iter().enumerate() {
-        if todo.project != current_project {
-            current_project = &todo.project;
-            println!("\n  ## {}", current_project);
+    let actionable = store
+        .listed_todos()?
+        .into_iter()
+        .filter(|(_, t)| t.is_actionable())
+        .collect();
+    match pick_listed(actionable, "Select a task to focus on:")? {
+        Some(todo) => store.target(&todo.id),
+        None => {
+            anyhow::bail!("No actionable tasks. Add a task first with: ambrogio tasks add <name>")
         }
-        println!("  {}. {}", i + 1, todo.description);
-    }
-
-    if !projects.is_empty() {
-        println!("\n  {}. Create a new task", open.len() + 1);
     }
+}
 
-    let max = if projects.is_empty() {
-        open.len()
-    } else {
-        open.len() + 1
-    };
+fn select_or_create_task(store: &TodoStore) -> Result<Target> {
+    let listed = store.listed_todos()?;
+    // Numbered after every listed task, so it never stands for one of them
+    let create = listed.len() + 1;
+    let open: Vec<(usize, todo::Todo)> = listed
+        .into_iter()
+        .filter(|(_, t)| t.is_actionable())
+        .collect();
+    let projects = store.projects()?;
 
-    if max == 0 {
+    if open.is_empty() && projects.is_empty() {
         anyhow::bail!(
             "No open tasks and no projects. Add a project first with: ambrogio projects add <name>"
         );
     }
 
-    let selection = loop {
-        print!("Enter number: ");
-        io::stdout().flush()?;
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+    print_open_todos_for_selection("Select a task to focus on:", &open);
 
-        match input.trim().parse::<usize>() {
-            Ok(n) if n >= 1 && n <= max => break n - 1,
-            _ => println!("Please enter a number between 1 and {}", max),
-        }
-    };
+    let mut numbers: Vec<usize> = open.iter().map(|(number, _)| *number).collect();
+    if !projects.is_empty() {
+        println!("\n  {}. Create a new task", create);
+        numbers.push(create);
+    }
 
-    if selection < open.len() {
-        return Ok((selection, open[selection].description.clone()));
+    let selection = read_todo_number(&numbers)?;
+    if let Some((_, todo)) = open.get(selection) {
+        return store.target(&todo.id);
     }
 
     print!("Task description: ");
@@ -337,32 +878,26 @@ fn select_or_create_task(store: &TodoStore) -> Result<(usize, String)> {
     let items: Vec<&str> = projects.iter().map(|p| p.as_str()).collect();
     let project_idx = prompt_selection("Select a project:", &items)?;
 
-    store.add(&projects[project_idx], &description)?;
+    let id = store.add(&projects[project_idx], &description)?;
     println!("Added to {}: {}", projects[project_idx], description);
 
-    let open = store.open_todos()?;
-    let new_index = open
-        .iter()
-        .position(|t| t.description == description && t.project == projects[project_idx])
-        .ok_or_else(|| anyhow::anyhow!("Failed to find newly created task"))?;
-
-    Ok((new_index, description))
+    store.target(&id)
 }
 
 async fn run_pomodoro(action: PomodoroAction) -> Result<()> {
     match action {
-        PomodoroAction::Start => {
+        PomodoroAction::Start { task } => {
             let file_config = FileConfig::from_env()?;
             let store = TodoStore::new(file_config.todos_path);
 
-            let (mut selection, mut description) = select_task(&store)?;
+            let mut target = select_task(&store, task)?;
 
             loop {
                 let started_at = Local::now().naive_local();
-                let outcome = pomodoro::run(&description).await?;
+                let outcome = pomodoro::run(&target.description, target.minutes).await?;
                 let cancelled = outcome == pomodoro::Outcome::Cancelled;
 
-                store.add_pomodoro(selection, started_at, cancelled)?;
+                store.add_pomodoro(&target, started_at, cancelled)?;
 
                 if cancelled {
                     break;
@@ -377,9 +912,7 @@ async fn run_pomodoro(action: PomodoroAction) -> Result<()> {
 
                 hooks::run("break", "stop")?;
 
-                let result = select_or_create_task(&store)?;
-                selection = result.0;
-                description = result.1;
+                target = select_or_create_task(&store)?;
             }
         }
     }
@@ -18,8 +18,12 @@ pub fn format_countdown(remaining: Duration) -> String {
     format!("{:02}:{:02}", minutes, seconds)
 }
 
-pub async fn run(description: &str) -> Result<Outcome> {
-    run_timer(POMODORO_DURATION, "🍅", description).await
+/// Runs a pomodoro of `minutes`, or of the default 25 minutes.
+pub async fn run(description: &str, minutes: Option<u32>) -> Result<Outcome> {
+    let duration = minutes.map_or(POMODORO_DURATION, |m| {
+        Duration::from_secs(u64::from(m) * 60)
+    });
+    run_timer(duration, "🍅", description).await
 }
 
 pub async fn run_break() -> Result<Outcome> {
@@ -0,0 +1,165 @@
+use anyhow::Result;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How long to wait for another process to release a lock.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+fn sibling(path: &Path, prefix: &str, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!("{}{}{}", prefix, name, suffix))
+}
+
+/// Replaces the file at `path` with `contents` by writing a temporary file
+/// next to it and renaming it over the original, so a crash leaves either
+/// the old or the new content, never a truncated file. A symlink is
+/// followed, so the file it points to is replaced and the link kept, and
+/// the file keeps its permissions.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let path = match fs::canonicalize(path) {
+        Ok(target) => target,
+        Err(e) if e.kind() == ErrorKind::NotFound => path.to_path_buf(),
+        Err(e) => return Err(e.into()),
+    };
+    let permissions = fs::metadata(&path).ok().map(|m| m.permissions());
+
+    let temp = sibling(&path, ".", ".tmp");
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, &path))
+        .and_then(|_| sync_parent(&path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    Ok(result?)
+}
+
+/// Flushes the directory holding `path`, so a rename into it survives a
+/// crash.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+/// An advisory lock on a file, held on a `<file>.lock` sibling until
+/// dropped. The lock file itself stays; the operating system releases the
+/// lock when the process exits, so a crash cannot leave a stale one behind.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    pub fn acquire(path: &Path) -> Result<Self> {
+        Self::acquire_within(path, LOCK_TIMEOUT)
+    }
+
+    fn acquire_within(path: &Path, timeout: Duration) -> Result<Self> {
+        let lock_path = sibling(path, "", ".lock");
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)?;
+
+        let started = SystemTime::now();
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(FileLock { _file: file }),
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+
+            if started.elapsed().unwrap_or_default() >= timeout {
+                anyhow::bail!("'{}' is locked by another ambrogio process", path.display());
+            }
+            thread::sleep(LOCK_RETRY_INTERVAL);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn write_atomic_replaces_content_without_leftovers() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("todos.md");
+        fs::write(&path, "old content that is longer").unwrap();
+
+        write_atomic(&path, "new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_symlinks_and_permissions() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = TempDir::new().unwrap();
+        let target = dir.path().join("synced").join("todos.md");
+        fs::create_dir(target.parent().unwrap()).unwrap();
+        fs::write(&target, "old").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        let link = dir.path().join("todos.md");
+        symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "new").unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn lock_is_exclusive_until_dropped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("todos.md");
+
+        let lock = FileLock::acquire(&path).unwrap();
+        assert!(dir.path().join("todos.md.lock").exists());
+
+        let second = FileLock::acquire_within(&path, Duration::ZERO);
+        assert!(second.unwrap_err().to_string().contains("locked"));
+
+        drop(lock);
+        assert!(FileLock::acquire_within(&path, Duration::ZERO).is_ok());
+    }
+
+    #[test]
+    fn lock_file_left_behind_does_not_block() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("todos.md");
+        fs::write(dir.path().join("todos.md.lock"), "12345\n").unwrap();
+
+        let lock = FileLock::acquire_within(&path, Duration::ZERO);
+        assert!(lock.is_ok());
+    }
+}
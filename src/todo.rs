@@ -1,478 +1,3419 @@
-use anyhow::Result;
-use chrono::NaiveDateTime;
+use anyhow::{Context, Result};
+use chrono::{Days, Local, NaiveDate, NaiveDateTime};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
 
-#[derive(Debug, PartialEq)]
+use crate::dates::{format_date, Recurrence};
+use crate::document::{
+    generate_id, is_within, name_parts, parent_name, unescape_title, Colour, Document, Priority,
+    Project, ProjectMeta, Status, Task, TIMESTAMP_FORMAT,
+};
+use crate::journal::{Change, Entry, Journal};
+use crate::storage::{write_atomic, FileLock};
+
+/// Journal operation of the ID migration in `load_all()`. It is not something
+/// the user did, so `undo` reverts it along with the operation before it.
+const ASSIGN_IDS: &str = "assign_ids";
+
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct Todo {
+    pub id: String,
     pub description: String,
     pub done: bool,
+    /// Handed off to someone else, `- [>]`
+    pub waiting: bool,
     pub project: String,
+    pub priority: Option<Priority>,
+    pub due: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    /// Day the task (or its parent) was snoozed until
+    pub start: Option<NaiveDate>,
+    pub recurrence: Option<Recurrence>,
+    /// Expected number of pomodoros
+    pub estimate: Option<usize>,
+    /// Completed and cancelled pomodoros spent on it
+    pub pomodoros: (usize, usize),
+    pub tags: Vec<String>,
+    pub contexts: Vec<String>,
+    /// ID of the task this is a subtask of
+    pub parent: Option<String>,
+    /// Nesting level, 0 for top-level tasks
+    pub depth: usize,
+    /// Done and total number of direct subtasks, if there are any
+    pub progress: Option<(usize, usize)>,
+    /// For done tasks, the day they were completed. Tasks completed before
+    /// completion times were recorded fall back to the day of their latest 🍅.
+    pub finished: Option<NaiveDate>,
+    /// For tasks in the trash, when they were deleted
+    pub deleted: Option<NaiveDateTime>,
+    /// Metadata of its project, see `project_meta`
+    pub project_meta: ProjectMeta,
+    /// IDs of the open tasks that must be done before this one
+    pub blocked_by: Vec<String>,
+    /// Text of its 📝 notes
+    pub notes: Vec<String>,
 }
 
-fn parse_todo_line(line: &str) -> Option<(String, bool)> {
-    let trimmed = line.trim();
-    if let Some(desc) = trimmed.strip_prefix("- [ ] ") {
-        Some((desc.to_string(), false))
-    } else {
-        trimmed
-            .strip_prefix("- [x] ")
-            .map(|desc| (desc.to_string(), true))
+impl Todo {
+    fn from_task(
+        project: &Project,
+        meta: &ProjectMeta,
+        task: &Task,
+        parent: Option<&Task>,
+        depth: usize,
+    ) -> Self {
+        let total = task.subtasks().count();
+        let done = task.subtasks().filter(|t| t.done).count();
+        Todo {
+            id: task.id.clone().unwrap_or_default(),
+            description: task.description.clone(),
+            done: task.done,
+            waiting: task.waiting,
+            project: project.name.clone(),
+            priority: task.priority,
+            due: task.due,
+            scheduled: task.scheduled,
+            start: task.start,
+            recurrence: task.recurrence,
+            estimate: task.estimate,
+            pomodoros: task.pomodoros(),
+            tags: task.tags(),
+            contexts: task.contexts(),
+            parent: parent.and_then(|p| p.id.clone()),
+            depth,
+            progress: (total > 0).then_some((done, total)),
+            finished: task.finished_at().map(|at| at.date()),
+            deleted: task.deleted_at(),
+            project_meta: meta.clone(),
+            blocked_by: task.blocked_by.clone(),
+            notes: task.notes(),
+        }
+    }
+
+    /// Whether the task is hidden until a day after `today`.
+    pub fn is_snoozed(&self, today: NaiveDate) -> bool {
+        self.start.is_some_and(|start| start > today)
+    }
+
+    /// Whether the task can be worked on now.
+    pub fn is_actionable(&self) -> bool {
+        !self.done && !self.waiting && self.blocked_by.is_empty()
     }
-}
 
-fn parse_project_header(line: &str) -> Option<String> {
-    line.strip_prefix("## ").map(|name| name.trim().to_string())
+    /// The earliest of the due and scheduled dates.
+    fn next_date(&self) -> Option<NaiveDate> {
+        [self.due, self.scheduled].into_iter().flatten().min()
+    }
 }
 
-fn find_open_todo_line(lines: &[&str], open_index: usize) -> Result<usize> {
-    let mut open_count = 0;
-    for (i, line) in lines.iter().enumerate() {
-        if line.trim().starts_with("- [ ] ") {
-            if open_count == open_index {
-                return Ok(i);
+impl fmt::Display for Todo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(priority) = self.priority {
+            write!(f, "({}) ", priority)?;
+        }
+        write!(f, "{} ({})", self.description, self.id)?;
+        if let Some((done, total)) = self.progress {
+            write!(f, " [{}/{}]", done, total)?;
+        }
+        if let Some(due) = self.due {
+            write!(f, " [due {}]", format_date(due))?;
+        }
+        if let Some(scheduled) = self.scheduled {
+            write!(f, " [scheduled {}]", format_date(scheduled))?;
+        }
+        if let Some(start) = self.start {
+            write!(f, " [snoozed until {}]", format_date(start))?;
+        }
+        if let Some(recurrence) = self.recurrence {
+            write!(f, " [every {}]", recurrence)?;
+        }
+        if let Some(estimate) = self.estimate {
+            let (completed, cancelled) = self.pomodoros;
+            write!(f, " [🍅 {}/{}", completed, estimate)?;
+            if cancelled > 0 {
+                write!(f, ", {} cancelled", cancelled)?;
             }
-            open_count += 1;
+            write!(f, "]")?;
         }
+        if !self.blocked_by.is_empty() {
+            write!(f, " [blocked by {}]", self.blocked_by.join(", "))?;
+        }
+        if self.waiting {
+            write!(f, " [waiting]")?;
+        }
+        if let Some(finished) = self.finished {
+            write!(f, " [done {}]", format_date(finished))?;
+        }
+        if let Some(deleted) = self.deleted {
+            write!(f, " [deleted {}]", deleted.format(TIMESTAMP_FORMAT))?;
+        }
+        Ok(())
     }
-    anyhow::bail!("Todo index {} out of bounds", open_index)
 }
 
-fn write_lines(path: &Path, lines: &[String], trailing_newline: bool) -> Result<()> {
-    let mut output = lines.join("\n");
-    if trailing_newline {
-        output.push('\n');
+/// Fields of a task about to be added with `TodoStore::add_todo`.
+#[derive(Debug, Default)]
+pub struct NewTodo {
+    pub description: String,
+    pub priority: Option<Priority>,
+    pub due: Option<NaiveDate>,
+    pub scheduled: Option<NaiveDate>,
+    pub recurrence: Option<Recurrence>,
+    pub estimate: Option<usize>,
+    /// IDs or descriptions of the tasks blocking it
+    pub blocked_by: Vec<String>,
+}
+
+/// A task picked for a long-running session such as a pomodoro, remembered
+/// together with the state of `todos.md` at that time, so the task can be
+/// found again if the file is edited in the meantime.
+#[derive(Debug, Clone)]
+pub struct Target {
+    pub id: String,
+    pub description: String,
+    /// Pomodoro length set on the task's project, in minutes
+    pub minutes: Option<u32>,
+    fingerprint: u64,
+}
+
+fn fingerprint(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The current ID of `target`. If the file changed since the target was
+/// picked, the task is looked up again by ID and then by description among
+/// tasks that had no ID of their own, and must be unambiguous. Tasks given a
+/// new ID avoid those in `taken`.
+fn resolve(doc: &mut Document, target: &Target, mut taken: HashSet<String>) -> Result<String> {
+    if fingerprint(&doc.to_string()) == target.fingerprint {
+        return Ok(target.id.clone());
+    }
+
+    // Tasks whose ID was removed by hand get a new one and can still match.
+    // Any other task with the same description, like an earlier instance of
+    // a recurring one, is a different task.
+    let had_id = doc.ids();
+    doc.assign_missing_ids(&mut taken);
+    let by_id = doc
+        .tasks()
+        .filter(|(_, t)| t.id.as_deref() == Some(target.id.as_str()))
+        .count();
+    if by_id == 1 {
+        return Ok(target.id.clone());
+    }
+
+    let by_description: Vec<String> = doc
+        .tasks()
+        .filter(|(_, t)| t.description == target.description)
+        .filter_map(|(_, t)| t.id.clone())
+        .filter(|id| !had_id.contains(id))
+        .collect();
+    match by_description.as_slice() {
+        [id] => Ok(id.clone()),
+        [] => anyhow::bail!(
+            "Task '{}' ({}) is gone: todos.md was changed in the meantime",
+            target.description,
+            target.id
+        ),
+        _ => anyhow::bail!(
+            "Task '{}' ({}) is ambiguous: todos.md was changed in the meantime",
+            target.description,
+            target.id
+        ),
     }
-    fs::write(path, output)?;
-    Ok(())
 }
 
-fn find_section_end(lines: &[&str], header_index: usize) -> usize {
-    for (i, line) in lines.iter().enumerate().skip(header_index + 1) {
-        if line.starts_with("## ") {
-            return i;
-        }
+/// Side effects of `TodoStore::complete`.
+#[derive(Debug, Default)]
+pub struct Completion {
+    /// The next occurrence created for a recurring task
+    pub next: Option<Todo>,
+    /// The still open parent, when this completed its last open subtask
+    pub parent: Option<Todo>,
+    /// Tasks that were only waiting for this one
+    pub unblocked: Vec<Todo>,
+}
+
+/// A task found by `TodoStore::search`.
+#[derive(Debug)]
+pub struct Match {
+    pub todo: Todo,
+    /// Its number in `tasks list` and the pickers, for tasks listed there
+    pub index: Option<usize>,
+    /// Whether it was found in `archive.md`
+    pub archived: bool,
+    /// Its notes containing one of the search terms
+    pub notes: Vec<String>,
+}
+
+/// What `TodoStore::search` found.
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    /// Projects of `todos.md` whose name matches
+    pub projects: Vec<String>,
+    pub matches: Vec<Match>,
+}
+
+impl SearchResults {
+    pub fn is_empty(&self) -> bool {
+        self.projects.is_empty() && self.matches.is_empty()
     }
-    lines.len()
 }
 
-pub struct TodoStore {
-    path: PathBuf,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateFilter {
+    /// Due before today
+    Overdue,
+    /// Due or scheduled today or earlier
+    Today,
+    /// Due or scheduled within the next seven days, or earlier
+    Week,
 }
 
-impl TodoStore {
-    pub fn new(path: PathBuf) -> Self {
-        Self { path }
+fn has_label(labels: &[String], wanted: &str) -> bool {
+    labels.iter().any(|l| l.eq_ignore_ascii_case(wanted))
+}
+
+/// Criteria for `print_open_todos` and `print_done_todos`. The default
+/// matches every task.
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub date: Option<DateFilter>,
+    /// Only tasks finished on or after this day
+    pub since: Option<NaiveDate>,
+    /// Every one of these tags must be present (case-insensitive)
+    pub tags: Vec<String>,
+    /// Every one of these contexts must be present (case-insensitive)
+    pub contexts: Vec<String>,
+    /// Only tasks in this project or its sub-projects
+    pub project: Option<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, todo: &Todo, today: NaiveDate) -> bool {
+        self.project
+            .as_deref()
+            .is_none_or(|project| is_within(&todo.project, project))
+            && self.tags.iter().all(|t| has_label(&todo.tags, t))
+            && self.contexts.iter().all(|c| has_label(&todo.contexts, c))
+            && self
+                .since
+                .is_none_or(|since| todo.finished.is_some_and(|f| f >= since))
+            && self.matches_date(todo, today)
     }
 
-    pub fn projects(&self) -> Result<Vec<String>> {
-        if !self.path.exists() {
-            return Ok(Vec::new());
+    fn matches_date(&self, todo: &Todo, today: NaiveDate) -> bool {
+        match self.date {
+            None => true,
+            Some(DateFilter::Overdue) => todo.due.is_some_and(|due| due < today),
+            Some(DateFilter::Today) => todo.next_date().is_some_and(|d| d <= today),
+            Some(DateFilter::Week) => todo.next_date().is_some_and(|d| d < today + Days::new(7)),
         }
+    }
+}
 
-        let content = fs::read_to_string(&self.path)?;
-        Ok(content.lines().filter_map(parse_project_header).collect())
+/// Sorts todos by priority within each project, keeping projects and
+/// equally prioritised todos in file order. Todos without a priority go last.
+/// Subtasks stay below their parent and are sorted among their siblings.
+pub fn sort_by_priority(todos: Vec<Todo>) -> Vec<Todo> {
+    let mut groups: Vec<Vec<Todo>> = Vec::new();
+    for todo in todos {
+        match groups.last_mut() {
+            Some(group) if group[0].project == todo.project => group.push(todo),
+            _ => groups.push(vec![todo]),
+        }
     }
+    groups.into_iter().flat_map(sort_siblings).collect()
+}
 
-    pub fn add_project(&self, name: &str) -> Result<()> {
-        let header = format!("## {}\n", name);
+/// Sorts the blocks starting at the shallowest todos, recursing into what
+/// follows each of them.
+fn sort_siblings(todos: Vec<Todo>) -> Vec<Todo> {
+    let Some(depth) = todos.iter().map(|t| t.depth).min() else {
+        return todos;
+    };
+
+    let mut blocks: Vec<Vec<Todo>> = Vec::new();
+    for todo in todos {
+        match blocks.last_mut() {
+            Some(block) if todo.depth > depth => block.push(todo),
+            _ => blocks.push(vec![todo]),
+        }
+    }
+    blocks.sort_by_key(|b| (b[0].priority.is_none(), b[0].priority));
+
+    blocks
+        .into_iter()
+        .flat_map(|mut block| {
+            let children = block.split_off(1);
+            block.into_iter().chain(sort_siblings(children))
+        })
+        .collect()
+}
 
-        if self.path.exists() {
-            let content = fs::read_to_string(&self.path)?;
+/// Prints how many pomodoros the todos with an estimate took compared to
+/// it, per task and per project in order of appearance, with totals.
+pub fn print_estimates(todos: &[Todo]) {
+    let mut projects: Vec<(&str, Vec<&Todo>)> = Vec::new();
+    for todo in todos.iter().filter(|t| t.estimate.is_some()) {
+        match projects
+            .iter_mut()
+            .find(|(project, _)| *project == todo.project)
+        {
+            Some((_, tasks)) => tasks.push(todo),
+            None => projects.push((&todo.project, vec![todo])),
+        }
+    }
 
-            if content
-                .lines()
-                .any(|l| parse_project_header(l).as_deref() == Some(name))
-            {
-                anyhow::bail!("Project '{}' already exists", name);
-            }
+    if projects.is_empty() {
+        println!("No completed tasks with an estimate.");
+        return;
+    }
 
-            let separator = if content.ends_with('\n') || content.is_empty() {
-                ""
-            } else {
-                "\n"
-            };
-            fs::write(&self.path, format!("{}{}{}", content, separator, header))?;
-        } else {
-            if let Some(parent) = self.path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            fs::write(&self.path, header)?;
+    let (mut estimated, mut actual) = (0, 0);
+    for (project, tasks) in &projects {
+        println!("\n  ## {}", project);
+        let (mut project_estimated, mut project_actual) = (0, 0);
+        for todo in tasks {
+            let estimate = todo.estimate.unwrap_or_default();
+            let (completed, cancelled) = todo.pomodoros;
+            println!(
+                "  {} ({}): {}, {} cancelled",
+                todo.description,
+                todo.id,
+                comparison(estimate, completed),
+                cancelled
+            );
+            project_estimated += estimate;
+            project_actual += completed;
         }
-
-        Ok(())
+        println!("  Total: {}", comparison(project_estimated, project_actual));
+        estimated += project_estimated;
+        actual += project_actual;
     }
+    println!("\nOverall: {}", comparison(estimated, actual));
+}
 
-    pub fn delete_project(&self, name: &str) -> Result<()> {
-        let content = fs::read_to_string(&self.path)?;
-        let lines: Vec<&str> = content.lines().collect();
+/// Prints the matching projects, then the matching tasks under their
+/// project, numbered as in the `tasks complete` prompt when they appear
+/// there, with the notes that matched below them.
+pub fn print_search(results: &SearchResults) {
+    if !results.projects.is_empty() {
+        println!("Projects: {}", results.projects.join(", "));
+    }
 
-        let header_index = lines
-            .iter()
-            .position(|l| parse_project_header(l).as_deref() == Some(name))
-            .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", name))?;
+    let mut current = None;
+    for m in &results.matches {
+        if current != Some((m.todo.project.as_str(), m.archived)) {
+            let source = if m.archived { " (archive)" } else { "" };
+            println!("\n  ## {}{}", m.todo.project, source);
+            current = Some((m.todo.project.as_str(), m.archived));
+        }
+        match m.index {
+            Some(index) => println!("  {}. {}", index, m.todo),
+            None => println!("  -  {}", m.todo),
+        }
+        for note in &m.notes {
+            println!("       📝 {}", note);
+        }
+    }
+}
 
-        let section_end = find_section_end(&lines, header_index);
+fn comparison(estimated: usize, actual: usize) -> String {
+    format!(
+        "{} estimated, {} done ({}%)",
+        estimated,
+        actual,
+        actual * 100 / estimated
+    )
+}
 
-        let mut new_lines: Vec<String> = Vec::new();
-        for (i, line) in lines.iter().enumerate() {
-            if i < header_index || i >= section_end {
-                new_lines.push(line.to_string());
+/// Counts open todos per `#tag` and `@context`, sorted by label. Labels
+/// differing only in case are counted together under their first spelling.
+pub fn label_counts(todos: &[Todo]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for todo in todos {
+        let tags = todo.tags.iter().map(|t| format!("#{}", t));
+        let contexts = todo.contexts.iter().map(|c| format!("@{}", c));
+        for label in tags.chain(contexts) {
+            match counts
+                .iter_mut()
+                .find(|(l, _)| l.eq_ignore_ascii_case(&label))
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((label, 1)),
             }
         }
+    }
+    counts.sort_by_key(|(label, _)| label.to_lowercase());
+    counts
+}
 
-        write_lines(&self.path, &new_lines, content.ends_with('\n'))
+fn colour_code(colour: Colour) -> &'static str {
+    match colour {
+        Colour::Red => "\x1b[31m",
+        Colour::Green => "\x1b[32m",
+        Colour::Yellow => "\x1b[33m",
+        Colour::Blue => "\x1b[34m",
+        Colour::Magenta => "\x1b[35m",
+        Colour::Cyan => "\x1b[36m",
     }
+}
 
-    pub fn add(&self, project: &str, description: &str) -> Result<()> {
-        let content = fs::read_to_string(&self.path)?;
-        let lines: Vec<&str> = content.lines().collect();
+fn priority_colour(priority: Priority) -> &'static str {
+    match priority {
+        Priority::A => "\x1b[31m",
+        Priority::B => "\x1b[33m",
+        Priority::C => "\x1b[34m",
+    }
+}
 
-        let header_index = lines
-            .iter()
-            .position(|l| parse_project_header(l).as_deref() == Some(project))
-            .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project))?;
+/// Prints todos under their `## Project` headings, numbered from 1, with
+/// sub-projects and subtasks indented below their parent. Prioritised todos
+/// are coloured when writing to a terminal.
+pub fn print_grouped(todos: &[Todo]) {
+    print_lines(todos.iter().enumerate().map(|(i, todo)| (i + 1, todo)));
+}
 
-        let section_end = find_section_end(&lines, header_index);
+/// Prints todos like `print_grouped`, keeping the numbers they come with.
+pub fn print_numbered(todos: &[(usize, Todo)]) {
+    print_lines(todos.iter().map(|(number, todo)| (*number, todo)));
+}
 
-        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
-        new_lines.insert(section_end, format!("- [ ] {}", description));
+fn print_lines<'a>(todos: impl Iterator<Item = (usize, &'a Todo)>) {
+    let colour = io::stdout().is_terminal();
+    let mut current_project = "";
+    for (number, todo) in todos {
+        if todo.project != current_project {
+            let heading_colour = todo.project_meta.colour.filter(|_| colour);
+            print_headings(current_project, &todo.project, heading_colour);
+            current_project = &todo.project;
+        }
+        let level = name_parts(&todo.project).len() - 1;
+        let indent = format!("{}{}", "  ".repeat(level), "   ".repeat(todo.depth));
+        match todo.priority {
+            _ if colour && !todo.blocked_by.is_empty() => {
+                println!("  {}\x1b[2m{}. {}\x1b[0m", indent, number, todo)
+            }
+            Some(priority) if colour => println!(
+                "  {}{}{}. {}\x1b[0m",
+                indent,
+                priority_colour(priority),
+                number,
+                todo
+            ),
+            _ => println!("  {}{}. {}", indent, number, todo),
+        }
+    }
+}
 
-        write_lines(&self.path, &new_lines, content.ends_with('\n'))
+/// Prints the heading of `project`, indented by its level, preceded by
+/// those of its parents that were not printed for `previous`.
+fn print_headings(previous: &str, project: &str, colour: Option<Colour>) {
+    let (start, end) = match colour {
+        Some(colour) => (colour_code(colour), "\x1b[0m"),
+        None => ("", ""),
+    };
+    let parts = name_parts(project);
+    for level in 0..parts.len() {
+        let name = parts[..=level].join("/");
+        if level + 1 < parts.len() && is_within(previous, &name) {
+            continue;
+        }
+        println!(
+            "\n  {}{}{} {}{}",
+            "  ".repeat(level),
+            start,
+            "#".repeat(level + 2),
+            unescape_title(parts[level]),
+            end
+        );
     }
+}
 
-    pub fn load_all(&self) -> Result<Vec<Todo>> {
-        if !self.path.exists() {
-            return Ok(Vec::new());
+/// The instance of a recurring task that follows `task`, due one period
+/// after its due date, or after `today` if it has none.
+fn next_occurrence(
+    task: &Task,
+    recurrence: Recurrence,
+    id: String,
+    today: NaiveDate,
+) -> Result<Task> {
+    let mut next = Task::new(&task.description, id);
+    next.priority = task.priority;
+    next.estimate = task.estimate;
+    next.recurrence = Some(recurrence);
+    next.due = Some(recurrence.next(task.due.unwrap_or(today))?);
+    next.scheduled = task.scheduled.map(|s| recurrence.next(s)).transpose()?;
+    Ok(next)
+}
+
+/// Whether `tasks list` shows the todo: open and not waiting, snoozed or in
+/// an archived project.
+fn is_listed(todo: &Todo, today: NaiveDate) -> bool {
+    !todo.done
+        && !todo.waiting
+        && !todo.is_snoozed(today)
+        && todo.project_meta.status != Some(Status::Archived)
+}
+
+/// Every task and subtask of the document, depth first.
+fn todos(doc: &Document) -> Vec<Todo> {
+    fn collect(
+        todos: &mut Vec<Todo>,
+        project: &Project,
+        meta: &ProjectMeta,
+        task: &Task,
+        parent: Option<&Task>,
+        depth: usize,
+    ) {
+        todos.push(Todo::from_task(project, meta, task, parent, depth));
+        for subtask in task.subtasks() {
+            collect(todos, project, meta, subtask, Some(task), depth + 1);
         }
+    }
 
-        let content = fs::read_to_string(&self.path)?;
-        let mut current_project = String::new();
-        let mut todos = Vec::new();
-
-        for line in content.lines() {
-            if let Some(project) = parse_project_header(line) {
-                current_project = project;
-            } else if let Some((description, done)) = parse_todo_line(line) {
-                if !current_project.is_empty() {
-                    todos.push(Todo {
-                        description,
-                        done,
-                        project: current_project.clone(),
-                    });
-                }
-            }
+    let mut todos = Vec::new();
+    for project in &doc.projects {
+        let meta = project_meta(doc, &project.name);
+        for task in project.tasks() {
+            collect(&mut todos, project, &meta, task, None, 0);
         }
+    }
 
-        Ok(todos)
+    let open: HashSet<String> = todos
+        .iter()
+        .filter(|t| !t.done)
+        .map(|t| t.id.clone())
+        .collect();
+    // Subtasks stay hidden as long as their parent is snoozed
+    let mut starts: HashMap<String, NaiveDate> = HashMap::new();
+    for todo in &mut todos {
+        todo.blocked_by.retain(|id| open.contains(id));
+        let parent_start = todo.parent.as_ref().and_then(|p| starts.get(p)).copied();
+        todo.start = todo.start.max(parent_start);
+        if let Some(start) = todo.start {
+            starts.insert(todo.id.clone(), start);
+        }
     }
+    todos
+}
 
-    pub fn open_todos(&self) -> Result<Vec<Todo>> {
-        Ok(self.load_all()?.into_iter().filter(|t| !t.done).collect())
+/// The ID of the task `reference` names: its ID, or else the description
+/// of exactly one open task, matched in full or in part, ignoring case.
+fn resolve_reference(doc: &Document, reference: &str) -> Result<String> {
+    if doc.tasks().any(|(_, t)| t.id.as_deref() == Some(reference)) {
+        return Ok(reference.to_string());
     }
 
-    pub fn add_pomodoro(
-        &self,
-        open_index: usize,
-        started_at: NaiveDateTime,
-        cancelled: bool,
-    ) -> Result<()> {
-        let content = fs::read_to_string(&self.path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let target = find_open_todo_line(&lines, open_index)?;
+    let needle = reference.to_lowercase();
+    let open: Vec<&Task> = doc
+        .tasks()
+        .map(|(_, t)| t)
+        .filter(|t| !t.done && t.id.is_some())
+        .collect();
+    let exact: Vec<&Task> = open
+        .iter()
+        .copied()
+        .filter(|t| t.description.to_lowercase() == needle)
+        .collect();
+    let matches = if exact.is_empty() {
+        open.into_iter()
+            .filter(|t| t.description.to_lowercase().contains(&needle))
+            .collect()
+    } else {
+        exact
+    };
+
+    match matches.as_slice() {
+        [task] => Ok(task.id.clone().unwrap_or_default()),
+        [] => anyhow::bail!("No task matches '{}'", reference),
+        _ => anyhow::bail!(
+            "'{}' matches several tasks ({}); use an ID",
+            reference,
+            matches
+                .iter()
+                .map(|t| format!("{} ({})", t.description, t.id.as_deref().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
 
-        let mut insert_at = target + 1;
-        while insert_at < lines.len() && lines[insert_at].starts_with("  ") {
-            insert_at += 1;
+/// Whether `from` is blocked by `to`, directly or through other tasks.
+fn depends_on(doc: &Document, from: &str, to: &str) -> bool {
+    let mut seen = HashSet::new();
+    let mut pending = vec![from.to_string()];
+    while let Some(id) = pending.pop() {
+        if id == to {
+            return true;
         }
+        if !seen.insert(id.clone()) {
+            continue;
+        }
+        if let Some((_, task)) = doc.tasks().find(|(_, t)| t.id.as_deref() == Some(&id)) {
+            pending.extend(task.blocked_by.iter().cloned());
+        }
+    }
+    false
+}
 
-        let status = if cancelled { " cancelled" } else { "" };
-        let pomodoro_line = format!("  - 🍅 {}{}", started_at.format("%Y-%m-%d %H:%M"), status);
+/// The metadata of `project`. Sub-projects take the status, colour and
+/// pomodoro length they leave unset from their parents.
+fn project_meta(doc: &Document, project: &str) -> ProjectMeta {
+    let mut meta = doc
+        .project(project)
+        .map(|p| p.meta.clone())
+        .unwrap_or_default();
+    let mut name = project;
+    while let Some(parent) = parent_name(name) {
+        if let Some(parent) = doc.project(parent) {
+            meta.status = meta.status.or(parent.meta.status);
+            meta.colour = meta.colour.or(parent.meta.colour);
+            meta.pomodoro = meta.pomodoro.or(parent.meta.pomodoro);
+        }
+        name = parent;
+    }
+    meta
+}
 
-        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
-        new_lines.insert(insert_at, pomodoro_line);
+fn new_task(doc: &Document, todo: &NewTodo, id: String) -> Result<Task> {
+    let mut task = Task::new(&todo.description, id);
+    task.priority = todo.priority.or(task.priority);
+    task.due = todo.due.or(task.due);
+    task.scheduled = todo.scheduled.or(task.scheduled);
+    task.recurrence = todo.recurrence.or(task.recurrence);
+    task.estimate = todo.estimate.or(task.estimate);
+    for reference in &todo.blocked_by {
+        let blocker = resolve_reference(doc, reference)?;
+        if !task.blocked_by.contains(&blocker) {
+            task.blocked_by.push(blocker);
+        }
+    }
+    Ok(task)
+}
 
-        write_lines(&self.path, &new_lines, content.ends_with('\n'))
+fn read_or_empty(path: &Path) -> Result<String> {
+    if path.exists() {
+        Ok(fs::read_to_string(path)?)
+    } else {
+        Ok(String::new())
     }
+}
+
+fn find_task<'a>(doc: &'a mut Document, id: &str) -> Result<&'a mut Task> {
+    doc.task_mut(id)
+        .ok_or_else(|| anyhow::anyhow!("Task '{}' not found", id))
+}
+
+/// Files besides `todos.md` that a transaction replaces, such as the
+/// archive and the trash.
+#[derive(Default)]
+struct SideFiles {
+    /// Written before `todos.md`
+    before: Vec<(PathBuf, String)>,
+    /// Written after `todos.md`
+    after: Vec<(PathBuf, String)>,
+}
 
-    pub fn add_note(&self, open_index: usize, text: &str) -> Result<()> {
-        let content = fs::read_to_string(&self.path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let target = find_open_todo_line(&lines, open_index)?;
+impl SideFiles {
+    /// Replaces `path` with `contents`.
+    fn replace(&mut self, path: PathBuf, contents: String) {
+        self.before.push((path, contents));
+    }
 
-        let mut insert_at = target + 1;
-        while insert_at < lines.len() && lines[insert_at].starts_with("  ") {
-            insert_at += 1;
+    /// Replaces `path`, a file tasks are moved to (`into`) or from,
+    /// exchanging them with `todos.md`. The file receiving the tasks is
+    /// written first, so if writing stops halfway they are in both files
+    /// rather than in neither.
+    fn move_tasks(&mut self, path: PathBuf, doc: &Document, into: bool) {
+        let file = (path, doc.to_string());
+        if into {
+            self.before.push(file);
+        } else {
+            self.after.push(file);
         }
+    }
+}
+
+pub struct TodoStore {
+    path: PathBuf,
+}
 
-        let note_line = format!("  - 📝 {}", text);
+impl TodoStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
 
-        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
-        new_lines.insert(insert_at, note_line);
+    fn read(&self) -> Result<Document> {
+        Ok(Document::parse(&fs::read_to_string(&self.path)?))
+    }
 
-        write_lines(&self.path, &new_lines, content.ends_with('\n'))
+    /// Runs a read-modify-write of `todos.md` while holding its lock,
+    /// recording it in the journal as `op`.
+    fn modify<T>(&self, op: &str, change: impl FnOnce(&mut Document) -> Result<T>) -> Result<T> {
+        self.transaction(op, |doc, _| change(doc))
     }
 
-    pub fn delete(&self, open_index: usize) -> Result<()> {
-        let content = fs::read_to_string(&self.path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let target = find_open_todo_line(&lines, open_index)?;
+    /// Runs a read-modify-write of `todos.md` while holding its lock. A
+    /// missing file reads as empty. `change` may also replace other files by
+    /// adding them to `files`. Nothing is written if `change` fails. The
+    /// changes are journaled as `op`.
+    fn transaction<T>(
+        &self,
+        op: &str,
+        change: impl FnOnce(&mut Document, &mut SideFiles) -> Result<T>,
+    ) -> Result<T> {
+        let _lock = FileLock::acquire(&self.path)?;
+        let mut doc = Document::parse(&read_or_empty(&self.path)?);
+        let mut side_files = SideFiles::default();
+        let result = change(&mut doc, &mut side_files)?;
+
+        let mut files = side_files.before;
+        files.push((self.path.clone(), doc.to_string()));
+        files.extend(side_files.after);
+        self.write(op, 0, files)?;
+        Ok(result)
+    }
 
-        let mut end = target + 1;
-        while end < lines.len() && lines[end].starts_with("  ") {
-            end += 1;
+    /// Replaces the files that changed, in order, then appends them to the
+    /// journal as `op`, so the journal never records a change that was not
+    /// written. Must be called with the lock held.
+    fn write(&self, op: &str, undoes: usize, files: Vec<(PathBuf, String)>) -> Result<()> {
+        let mut changes = Vec::new();
+        let mut writes = Vec::new();
+        for (path, contents) in files {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if let Some(change) = Change::between(&name, &read_or_empty(&path)?, &contents) {
+                changes.push(change);
+                writes.push((path, contents));
+            }
+        }
+        if changes.is_empty() {
+            return Ok(());
         }
 
-        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
-        new_lines.drain(target..end);
+        for (path, contents) in writes {
+            write_atomic(&path, &contents)?;
+        }
+        self.journal().append(&Entry {
+            at: Local::now().format(TIMESTAMP_FORMAT).to_string(),
+            op: op.to_string(),
+            undoes,
+            changes,
+        })
+    }
 
-        write_lines(&self.path, &new_lines, content.ends_with('\n'))
+    fn journal(&self) -> Journal {
+        Journal::new(self.path.with_file_name("journal.jsonl"))
     }
 
-    pub fn complete(&self, index: usize) -> Result<()> {
-        let content = fs::read_to_string(&self.path)?;
-        let lines: Vec<&str> = content.lines().collect();
-        let target = find_open_todo_line(&lines, index)?;
+    /// Reverts the last `count` journaled operations, newest first, and
+    /// returns them. ID migrations journaled in between are reverted too but
+    /// do not count. If a file was edited since so that an operation's lines
+    /// cannot be found, nothing is reverted.
+    pub fn undo(&self, count: usize) -> Result<Vec<Entry>> {
+        let _lock = FileLock::acquire(&self.path)?;
+        let mut undoable = self.journal().undoable()?;
+        let mut start = undoable.len();
+        let mut remaining = count;
+        while remaining > 0 && start > 0 {
+            start -= 1;
+            if undoable[start].op != ASSIGN_IDS {
+                remaining -= 1;
+            }
+        }
+        let mut entries = undoable.split_off(start);
+        if entries.iter().all(|entry| entry.op == ASSIGN_IDS) {
+            return Ok(Vec::new());
+        }
+        entries.reverse();
+
+        let mut files: Vec<(PathBuf, String)> = Vec::new();
+        for entry in &entries {
+            for change in entry.changes.iter().rev() {
+                let path = self.path.with_file_name(&change.file);
+                let index = match files.iter().position(|(p, _)| *p == path) {
+                    Some(index) => index,
+                    None => {
+                        let contents = read_or_empty(&path)?;
+                        files.push((path, contents));
+                        files.len() - 1
+                    }
+                };
+                files[index].1 = change.revert(&files[index].1).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Cannot undo {} from {}: '{}' was edited since",
+                        entry.op,
+                        entry.at,
+                        change.file
+                    )
+                })?;
+            }
+        }
+
+        self.write("undo", entries.len(), files)?;
+        Ok(entries)
+    }
 
-        let mut new_lines: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
-        new_lines[target] = new_lines[target].replacen("- [ ] ", "- [x] ", 1);
+    /// `archive.md`, next to `todos.md`.
+    pub fn archive_path(&self) -> PathBuf {
+        self.path.with_file_name("archive.md")
+    }
 
-        write_lines(&self.path, &new_lines, content.ends_with('\n'))
+    /// `trash.md`, next to `todos.md`.
+    pub fn trash_path(&self) -> PathBuf {
+        self.path.with_file_name("trash.md")
     }
 
-    pub fn print_open_todos(&self) -> Result<()> {
-        let todos = self.open_todos()?;
+    /// IDs in use in `doc`, `archive.md` and `trash.md`, so that a new task
+    /// never shares its ID with an archived or deleted one.
+    fn taken_ids(&self, doc: &Document) -> Result<HashSet<String>> {
+        let mut ids = doc.ids();
+        for path in [self.archive_path(), self.trash_path()] {
+            ids.extend(Document::parse(&read_or_empty(&path)?).ids());
+        }
+        Ok(ids)
+    }
 
-        if todos.is_empty() {
-            println!("No open tasks.");
+    /// Appends deleted tasks to their project in `trash.md`, renumbering
+    /// any whose ID is already in the trash.
+    fn put_in_trash(&self, files: &mut SideFiles, tasks: Vec<(String, Task)>) -> Result<()> {
+        if tasks.is_empty() {
             return Ok(());
         }
 
-        let mut current_project = String::new();
-        for (i, todo) in todos.iter().enumerate() {
-            if todo.project != current_project {
-                current_project = todo.project.clone();
-                println!("\n  ## {}", current_project);
-            }
-            println!("  {}. {}", i + 1, todo.description);
+        let trash_path = self.trash_path();
+        let mut trash = Document::parse(&read_or_empty(&trash_path)?);
+        let mut ids = trash.ids();
+        for (project, mut task) in tasks {
+            task.claim_ids(&mut ids);
+            trash.project_or_insert(&project).push_task(task);
         }
 
+        files.move_tasks(trash_path, &trash, true);
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+    /// Top-level tasks in the trash. Their subtasks are restored with them.
+    pub fn trashed(&self) -> Result<Vec<Todo>> {
+        let trash = Document::parse(&read_or_empty(&self.trash_path())?);
+        Ok(todos(&trash).into_iter().filter(|t| t.depth == 0).collect())
+    }
 
-    fn store_with_content(dir: &TempDir, content: &str) -> (TodoStore, PathBuf) {
-        let path = dir.path().join("todos.md");
-        fs::write(&path, content).unwrap();
-        (TodoStore::new(path.clone()), path)
+    /// Moves trashed top-level tasks matching `predicate` (given the
+    /// project they were deleted from) back to `todos.md`: under the task
+    /// they were a subtask of if it still exists, otherwise at the end of
+    /// their project, which is recreated if needed. Restored tasks whose ID
+    /// was taken in the meantime get a new one.
+    fn restore_where(&self, mut predicate: impl FnMut(&str, &Task) -> bool) -> Result<Vec<Todo>> {
+        self.transaction("restore", |doc, files| {
+            let trash_path = self.trash_path();
+            let mut trash = Document::parse(&read_or_empty(&trash_path)?);
+            let mut taken = Vec::new();
+            for project in &mut trash.projects {
+                let name = project.name.clone();
+                for task in project.take_tasks(|task| predicate(&name, task)) {
+                    taken.push((name.clone(), task));
+                }
+            }
+            if taken.is_empty() {
+                return Ok(Vec::new());
+            }
+            let used: Vec<String> = trash
+                .projects
+                .iter()
+                .filter(|p| p.tasks().next().is_some())
+                .map(|p| p.name.clone())
+                .collect();
+            trash
+                .projects
+                .retain(|p| used.iter().any(|name| is_within(name, &p.name)));
+
+            let mut ids = doc.ids();
+            ids.extend(trash.ids());
+            ids.extend(Document::parse(&read_or_empty(&self.archive_path())?).ids());
+            let mut restored = Vec::new();
+            for (project, mut task) in taken {
+                let parent = task.untrash();
+                task.claim_ids(&mut ids);
+                restored.push(task.id.clone().unwrap_or_default());
+                match parent.and_then(|parent| doc.task_mut(&parent)) {
+                    Some(parent) => parent.add_subtask(task),
+                    None => doc.project_or_insert(&project).push_task(task),
+                }
+            }
+            files.move_tasks(trash_path, &trash, false);
+
+            let todos = todos(doc);
+            Ok(restored
+                .iter()
+                .filter_map(|id| todos.iter().find(|t| &t.id == id).cloned())
+                .collect())
+        })
     }
 
-    #[test]
-    fn parses_open_todo_line() {
-        let (desc, done) = parse_todo_line("- [ ] buy milk").unwrap();
-        assert_eq!(desc, "buy milk");
-        assert!(!done);
+    /// Restores the trashed task with ID `id`, with everything below it.
+    pub fn restore(&self, id: &str) -> Result<Todo> {
+        self.restore_where(|_, task| task.id.as_deref() == Some(id))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Task '{}' not found in the trash", id))
     }
 
-    #[test]
-    fn parses_done_todo_line() {
-        let (desc, done) = parse_todo_line("- [x] buy milk").unwrap();
-        assert_eq!(desc, "buy milk");
-        assert!(done);
+    /// Restores every trashed task deleted from `project` or its
+    /// sub-projects.
+    pub fn restore_project(&self, project: &str) -> Result<Vec<Todo>> {
+        let restored = self.restore_where(|name, _| is_within(name, project))?;
+        if restored.is_empty() {
+            anyhow::bail!("Project '{}' not found in the trash", project);
+        }
+        Ok(restored)
     }
 
-    #[test]
-    fn ignores_non_todo_lines() {
-        assert!(parse_todo_line("# heading").is_none());
-        assert!(parse_todo_line("## Project").is_none());
-        assert!(parse_todo_line("some text").is_none());
-        assert!(parse_todo_line("").is_none());
+    /// Permanently removes everything in the trash. Returns the number of
+    /// top-level tasks removed.
+    pub fn empty_trash(&self) -> Result<usize> {
+        self.transaction("empty_trash", |_, files| {
+            let trash_path = self.trash_path();
+            let trash = Document::parse(&read_or_empty(&trash_path)?);
+            let count = trash.projects.iter().map(|p| p.tasks().count()).sum();
+            files.replace(trash_path, String::new());
+            Ok(count)
+        })
     }
 
-    #[test]
-    fn parses_project_header_line() {
-        assert_eq!(parse_project_header("## Work"), Some("Work".to_string()));
-        assert_eq!(
-            parse_project_header("## My Project"),
-            Some("My Project".to_string())
-        );
+    pub fn projects(&self) -> Result<Vec<String>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self.read()?.projects.into_iter().map(|p| p.name).collect())
     }
 
-    #[test]
-    fn ignores_non_header_lines() {
-        assert!(parse_project_header("# heading").is_none());
-        assert!(parse_project_header("- [ ] task").is_none());
-        assert!(parse_project_header("").is_none());
+    pub fn add_project(&self, name: &str) -> Result<()> {
+        self.modify("add_project", |doc| {
+            if doc.project(name).is_some() {
+                anyhow::bail!("Project '{}' already exists", name);
+            }
+
+            let project = Project::new(name);
+            if let Some(parent) = project.parent() {
+                if doc.project(parent).is_none() {
+                    anyhow::bail!(
+                        "Project '{}' not found (write '\\/' for a slash within a project title)",
+                        parent
+                    );
+                }
+            }
+            doc.push_project(project);
+            Ok(())
+        })
     }
 
-    #[test]
-    fn projects_returns_empty_for_missing_file() {
-        let dir = TempDir::new().unwrap();
-        let store = TodoStore::new(dir.path().join("todos.md"));
-        assert!(store.projects().unwrap().is_empty());
+    /// Projects with their own metadata, in file order.
+    pub fn project_details(&self) -> Result<Vec<(String, ProjectMeta)>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(self
+            .read()?
+            .projects
+            .into_iter()
+            .map(|p| (p.name, p.meta))
+            .collect())
     }
 
-    #[test]
-    fn projects_lists_all_project_names() {
-        let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n- [ ] task\n## Personal\n- [ ] task\n");
-        assert_eq!(store.projects().unwrap(), vec!["Work", "Personal"]);
+    /// Changes a project's metadata.
+    pub fn update_project(&self, name: &str, change: impl FnOnce(&mut ProjectMeta)) -> Result<()> {
+        self.modify("update_project", |doc| {
+            change(
+                &mut doc
+                    .project_mut(name)
+                    .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", name))?
+                    .meta,
+            );
+            Ok(())
+        })
     }
 
-    #[test]
-    fn add_project_creates_file_with_header() {
-        let dir = TempDir::new().unwrap();
-        let path = dir.path().join("todos.md");
-        let store = TodoStore::new(path.clone());
+    /// Moves a project and its sub-projects to `position` (0-based, or
+    /// last when past the end) among the projects sharing its parent.
+    pub fn reorder_project(&self, name: &str, position: usize) -> Result<()> {
+        self.modify("reorder_project", |doc| {
+            if !doc.reorder_project(name, position) {
+                anyhow::bail!("Project '{}' not found", name);
+            }
+            Ok(())
+        })
+    }
 
-        store.add_project("Work").unwrap();
+    /// Renames a project and its sub-projects in `todos.md`, and in
+    /// `archive.md` and `trash.md` where they also appear. There, tasks are
+    /// merged into an existing project of the new name. A sub-project keeps
+    /// its parent: only the last part of its name can change.
+    pub fn rename_project(&self, old: &str, new: &str) -> Result<()> {
+        let parent = |name: &str| Project::new(name).parent().map(String::from);
+        if parent(old) != parent(new) {
+            anyhow::bail!(
+                "Cannot rename '{}' to '{}': only the last part of a project name can change",
+                old,
+                new
+            );
+        }
+        let renamed = |name: &str| format!("{}{}", new, &name[old.len()..]);
 
-        let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Work\n");
+        self.transaction("rename_project", |doc, files| {
+            if doc.project(new).is_some() {
+                anyhow::bail!("Project '{}' already exists", new);
+            }
+            if doc.project(old).is_none() {
+                anyhow::bail!("Project '{}' not found", old);
+            }
+            for project in doc.projects.iter_mut().filter(|p| p.is_within(old)) {
+                let name = renamed(&project.name);
+                project.rename(&name);
+            }
+
+            for path in [self.archive_path(), self.trash_path()] {
+                let mut other = Document::parse(&read_or_empty(&path)?);
+                let names: Vec<String> = other
+                    .projects
+                    .iter()
+                    .filter(|p| p.is_within(old))
+                    .map(|p| p.name.clone())
+                    .collect();
+                if names.is_empty() {
+                    continue;
+                }
+
+                let merge = other.project(new).is_some();
+                for name in names {
+                    if !merge {
+                        if let Some(project) = other.project_mut(&name) {
+                            project.rename(&renamed(&name));
+                        }
+                    } else if let Some(mut project) = other.remove_project(&name) {
+                        let tasks = project.take_tasks(|_| true);
+                        let existing = other.project_or_insert(&renamed(&name));
+                        for task in tasks {
+                            existing.push_task(task);
+                        }
+                    }
+                }
+                files.replace(path, other.to_string());
+            }
+            Ok(())
+        })
     }
 
-    #[test]
-    fn add_project_appends_to_existing_file() {
-        let dir = TempDir::new().unwrap();
-        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task\n");
+    /// Removes a project and its sub-projects, moving their tasks to the
+    /// trash.
+    pub fn delete_project(&self, name: &str, now: NaiveDateTime) -> Result<()> {
+        self.transaction("delete_project", |doc, files| {
+            if doc.project(name).is_none() {
+                anyhow::bail!("Project '{}' not found", name);
+            }
 
-        store.add_project("Personal").unwrap();
+            let mut trashed = Vec::new();
+            while let Some(index) = doc.projects.iter().position(|p| p.is_within(name)) {
+                let mut project = doc.projects.remove(index);
+                for mut task in project.take_tasks(|_| true) {
+                    task.trash(now, None);
+                    trashed.push((project.name.clone(), task));
+                }
+            }
+            self.put_in_trash(files, trashed)
+        })
+    }
 
-        let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Work\n- [ ] task\n## Personal\n");
+    pub fn add(&self, project: &str, description: &str) -> Result<String> {
+        self.add_todo(
+            project,
+            &NewTodo {
+                description: description.to_string(),
+                ..NewTodo::default()
+            },
+        )
     }
 
-    #[test]
-    fn add_project_rejects_duplicate() {
-        let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n");
+    pub fn add_todo(&self, project: &str, todo: &NewTodo) -> Result<String> {
+        self.modify("add", |doc| {
+            let id = generate_id(&self.taken_ids(doc)?);
+            let task = new_task(doc, todo, id.clone())?;
 
-        let result = store.add_project("Work");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("already exists"));
+            doc.project_mut(project)
+                .ok_or_else(|| anyhow::anyhow!("Project '{}' not found", project))?
+                .push_task(task);
+
+            Ok(id)
+        })
+    }
+
+    /// Adds a subtask at the end of the task with ID `parent`.
+    pub fn add_subtask(&self, parent: &str, todo: &NewTodo) -> Result<String> {
+        self.modify("add", |doc| {
+            let id = generate_id(&self.taken_ids(doc)?);
+            let task = new_task(doc, todo, id.clone())?;
+            find_task(doc, parent)?.add_subtask(task);
+            Ok(id)
+        })
+    }
+
+    pub fn load_all(&self) -> Result<Vec<Todo>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        // Files written before task IDs existed are migrated on first read
+        self.modify(ASSIGN_IDS, |doc| {
+            doc.assign_missing_ids(&mut self.taken_ids(doc)?);
+            Ok(todos(doc))
+        })
+    }
+
+    /// Open todos, except snoozed ones and those of archived projects.
+    pub fn open_todos(&self) -> Result<Vec<Todo>> {
+        let today = Local::now().date_naive();
+        Ok(self
+            .unfinished_todos()?
+            .into_iter()
+            .filter(|t| !t.is_snoozed(today))
+            .collect())
+    }
+
+    /// Open todos snoozed until a day after today.
+    pub fn snoozed_todos(&self) -> Result<Vec<Todo>> {
+        let today = Local::now().date_naive();
+        Ok(self
+            .unfinished_todos()?
+            .into_iter()
+            .filter(|t| t.is_snoozed(today))
+            .collect())
+    }
+
+    fn unfinished_todos(&self) -> Result<Vec<Todo>> {
+        Ok(self
+            .load_all()?
+            .into_iter()
+            .filter(|t| !t.done && t.project_meta.status != Some(Status::Archived))
+            .collect())
+    }
+
+    /// Completed todos with an estimate, from `todos.md` and then
+    /// `archive.md`.
+    pub fn estimated_todos(&self) -> Result<Vec<Todo>> {
+        let archive = Document::parse(&read_or_empty(&self.archive_path())?);
+        Ok(self
+            .load_all()?
+            .into_iter()
+            .chain(todos(&archive))
+            .filter(|t| t.done && t.estimate.is_some())
+            .collect())
+    }
+
+    /// Tasks of `todos.md` and then `archive.md` whose description and
+    /// notes together contain every word of `query`, ignoring case, and
+    /// the projects whose name does.
+    pub fn search(&self, query: &str) -> Result<SearchResults> {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        if terms.is_empty() {
+            anyhow::bail!("Search query cannot be empty");
+        }
+        let contains_all = |text: &str| {
+            let text = text.to_lowercase();
+            terms.iter().all(|term| text.contains(term.as_str()))
+        };
+        let contains_any = |text: &str| {
+            let text = text.to_lowercase();
+            terms.iter().any(|term| text.contains(term.as_str()))
+        };
+
+        let projects = self
+            .projects()?
+            .into_iter()
+            .filter(|name| contains_all(name))
+            .collect();
+
+        let indexes: HashMap<String, usize> = self
+            .listed_todos()?
+            .into_iter()
+            .map(|(number, todo)| (todo.id, number))
+            .collect();
+        let archive = Document::parse(&read_or_empty(&self.archive_path())?);
+        let found = self
+            .load_all()?
+            .into_iter()
+            .map(|todo| (todo, false))
+            .chain(todos(&archive).into_iter().map(|todo| (todo, true)));
+
+        let mut matches = Vec::new();
+        for (todo, archived) in found {
+            let text = format!("{}\n{}", todo.description, todo.notes.join("\n"));
+            if !contains_all(&text) {
+                continue;
+            }
+            matches.push(Match {
+                index: (!archived)
+                    .then(|| indexes.get(&todo.id).copied())
+                    .flatten(),
+                archived,
+                notes: todo
+                    .notes
+                    .iter()
+                    .filter(|n| contains_any(n))
+                    .cloned()
+                    .collect(),
+                todo,
+            });
+        }
+
+        Ok(SearchResults { projects, matches })
+    }
+
+    /// Open todos that are not waiting on someone else, sorted by priority
+    /// and numbered from 1. `tasks list`, the pickers and `search` all
+    /// number tasks this way, also when showing only some of them, so a
+    /// number always means the same task.
+    pub fn listed_todos(&self) -> Result<Vec<(usize, Todo)>> {
+        let today = Local::now().date_naive();
+        let todos = self.load_all()?.into_iter().filter(|t| is_listed(t, today));
+        Ok(sort_by_priority(todos.collect())
+            .into_iter()
+            .enumerate()
+            .map(|(i, todo)| (i + 1, todo))
+            .collect())
+    }
+
+    /// Open todos handed off to someone else.
+    pub fn waiting_todos(&self) -> Result<Vec<Todo>> {
+        Ok(self
+            .open_todos()?
+            .into_iter()
+            .filter(|t| t.waiting)
+            .collect())
+    }
+
+    pub fn done_todos(&self) -> Result<Vec<Todo>> {
+        Ok(self.load_all()?.into_iter().filter(|t| t.done).collect())
+    }
+
+    pub fn find(&self, id: &str) -> Result<Todo> {
+        self.load_all()?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Task '{}' not found", id))
+    }
+
+    /// Remembers a task for `add_pomodoro`.
+    pub fn target(&self, id: &str) -> Result<Target> {
+        let doc = Document::parse(&fs::read_to_string(&self.path)?);
+        let (project, task) = doc
+            .tasks()
+            .find(|(_, t)| t.id.as_deref() == Some(id))
+            .ok_or_else(|| anyhow::anyhow!("Task '{}' not found", id))?;
+        Ok(Target {
+            id: id.to_string(),
+            description: task.description.clone(),
+            minutes: project_meta(&doc, &project.name).pomodoro,
+            fingerprint: fingerprint(&doc.to_string()),
+        })
+    }
+
+    /// Records a pomodoro on a task picked when it started. If `todos.md`
+    /// was edited since, the task is found again by ID or description;
+    /// when that is not possible nothing is written.
+    pub fn add_pomodoro(
+        &self,
+        target: &Target,
+        started_at: NaiveDateTime,
+        cancelled: bool,
+    ) -> Result<()> {
+        self.modify("add_pomodoro", |doc| {
+            let taken = self.taken_ids(doc)?;
+            let id = resolve(doc, target, taken).with_context(|| {
+                format!(
+                    "Pomodoro started at {} was not recorded",
+                    started_at.format(TIMESTAMP_FORMAT)
+                )
+            })?;
+            find_task(doc, &id)?.add_pomodoro(started_at, cancelled);
+            Ok(())
+        })
+    }
+
+    pub fn add_note(&self, id: &str, text: &str) -> Result<()> {
+        self.modify("add_note", |doc| {
+            find_task(doc, id)?.add_note(text);
+            Ok(())
+        })
+    }
+
+    /// Removes a task with everything below it, moving it to the trash.
+    pub fn delete(&self, id: &str, now: NaiveDateTime) -> Result<()> {
+        self.transaction("delete", |doc, files| {
+            let todo = todos(doc)
+                .into_iter()
+                .find(|t| t.id == id)
+                .ok_or_else(|| anyhow::anyhow!("Task '{}' not found", id))?;
+            let Some(mut task) = doc.remove_task(id) else {
+                anyhow::bail!("Task '{}' not found", id);
+            };
+            task.trash(now, todo.parent.as_deref());
+            self.put_in_trash(files, vec![(todo.project, task)])
+        })
+    }
+
+    /// Marks a task done. A recurring task hands its rule over to a new
+    /// open instance inserted right after it. Completing the last open
+    /// subtask reports the parent, which is left for the caller to complete.
+    pub fn complete(&self, id: &str, now: NaiveDateTime) -> Result<Completion> {
+        self.modify("complete", |doc| {
+            let waiting: Vec<String> = todos(doc)
+                .into_iter()
+                .filter(|t| t.blocked_by.iter().any(|b| b == id))
+                .map(|t| t.id)
+                .collect();
+            let new_id = generate_id(&self.taken_ids(doc)?);
+            let task = find_task(doc, id)?;
+            if task.done {
+                anyhow::bail!("Task '{}' is already complete", id);
+            }
+
+            task.complete(now);
+            let recurrence = task.recurrence.take();
+            if let Some(recurrence) = recurrence {
+                let next = next_occurrence(task, recurrence, new_id.clone(), now.date())?;
+                doc.insert_after(id, next);
+            }
+
+            let todos = todos(doc);
+            let find = |id: &str| todos.iter().find(|t| t.id == id).cloned();
+            Ok(Completion {
+                next: recurrence.and_then(|_| find(&new_id)),
+                parent: find(id)
+                    .and_then(|t| t.parent)
+                    .and_then(|parent| find(&parent))
+                    .filter(|p| !p.done && p.progress.is_some_and(|(done, total)| done == total)),
+                unblocked: todos
+                    .iter()
+                    .filter(|t| waiting.contains(&t.id) && t.blocked_by.is_empty())
+                    .cloned()
+                    .collect(),
+            })
+        })
+    }
+
+    /// Marks the task `id` as blocked by the one `reference` names (see
+    /// `resolve_reference`). Returns the blocking task.
+    pub fn block(&self, id: &str, reference: &str) -> Result<Todo> {
+        self.modify("block", |doc| {
+            let blocker = resolve_reference(doc, reference)?;
+            if blocker == id {
+                anyhow::bail!("A task cannot block itself");
+            }
+            if depends_on(doc, &blocker, id) {
+                anyhow::bail!(
+                    "Cannot block '{}' by '{}': it already waits for '{}'",
+                    id,
+                    blocker,
+                    id
+                );
+            }
+
+            let task = find_task(doc, id)?;
+            if !task.blocked_by.contains(&blocker) {
+                task.blocked_by.push(blocker.clone());
+            }
+            todos(doc)
+                .into_iter()
+                .find(|t| t.id == blocker)
+                .ok_or_else(|| anyhow::anyhow!("Task '{}' not found", blocker))
+        })
+    }
+
+    /// Removes the dependency of task `id` on the task `reference` names,
+    /// or all its dependencies without a reference.
+    pub fn unblock(&self, id: &str, reference: Option<&str>) -> Result<()> {
+        self.modify("unblock", |doc| {
+            let blocker = match reference {
+                Some(reference)
+                    if find_task(doc, id)?
+                        .blocked_by
+                        .iter()
+                        .any(|b| b == reference) =>
+                {
+                    Some(reference.to_string())
+                }
+                Some(reference) => Some(resolve_reference(doc, reference)?),
+                None => None,
+            };
+
+            let task = find_task(doc, id)?;
+            match blocker {
+                Some(blocker) if !task.blocked_by.contains(&blocker) => {
+                    anyhow::bail!("Task '{}' is not blocked by '{}'", id, blocker)
+                }
+                Some(blocker) => task.blocked_by.retain(|b| *b != blocker),
+                None => task.blocked_by.clear(),
+            }
+            Ok(())
+        })
+    }
+
+    /// The editable text of a task: its description and metadata tokens.
+    pub fn text(&self, id: &str) -> Result<String> {
+        Ok(find_task(&mut self.read()?, id)?.text())
+    }
+
+    /// Rewrites a task's description and metadata from `text`, keeping its
+    /// ID, state, sub-items and subtasks.
+    pub fn edit(&self, id: &str, text: &str) -> Result<()> {
+        if text.trim().is_empty() {
+            anyhow::bail!("Task description cannot be empty");
+        }
+
+        self.modify("edit", |doc| {
+            find_task(doc, id)?.edit(text);
+            Ok(())
+        })
+    }
+
+    /// Moves a task with all its sub-items and subtasks to the end of another
+    /// project. A subtask becomes a top-level task there.
+    pub fn move_task(&self, id: &str, project: &str) -> Result<()> {
+        self.modify("move", |doc| {
+            if doc.project(project).is_none() {
+                anyhow::bail!("Project '{}' not found", project);
+            }
+
+            let task = doc
+                .remove_task(id)
+                .ok_or_else(|| anyhow::anyhow!("Task '{}' not found", id))?;
+            if let Some(target) = doc.project_mut(project) {
+                target.push_task(task);
+            }
+            Ok(())
+        })
+    }
+
+    /// Moves a listed task so it is shown at `position` (0-based, or last
+    /// when past the end) among the listed tasks of its project, or of its
+    /// parent for subtasks. Tasks of another priority keep it from going
+    /// above or below them, which is an error. Returns the position.
+    pub fn reorder(&self, id: &str, position: usize) -> Result<usize> {
+        self.modify("reorder", |doc| {
+            let today = Local::now().date_naive();
+            let listed = sort_by_priority(
+                todos(doc)
+                    .into_iter()
+                    .filter(|t| is_listed(t, today))
+                    .collect(),
+            );
+            let Some(todo) = listed.iter().find(|t| t.id == id) else {
+                find_task(doc, id)?;
+                anyhow::bail!(
+                    "Task '{}' is not listed: it is done, waiting or snoozed",
+                    id
+                );
+            };
+            let others: Vec<&Todo> = listed
+                .iter()
+                .filter(|t| t.project == todo.project && t.parent == todo.parent && t.id != id)
+                .collect();
+
+            let rank = |t: &Todo| (t.priority.is_none(), t.priority);
+            let first = others.iter().filter(|t| rank(t) < rank(todo)).count();
+            let last = first + others.iter().filter(|t| rank(t) == rank(todo)).count();
+            let position = position.min(others.len());
+            if position < first {
+                anyhow::bail!(
+                    "Cannot move '{}' to position {}: tasks of a higher priority stay above it",
+                    todo.description,
+                    position + 1
+                );
+            }
+            if position > last {
+                anyhow::bail!(
+                    "Cannot move '{}' to position {}: tasks of a lower priority stay below it",
+                    todo.description,
+                    position + 1
+                );
+            }
+
+            // Tasks of the same priority are listed in file order
+            let moved = if position < last {
+                doc.reorder_task(id, &others[position].id, false)
+            } else if position > first {
+                doc.reorder_task(id, &others[position - 1].id, true)
+            } else {
+                true
+            };
+            if !moved {
+                anyhow::bail!("Task '{}' not found", id);
+            }
+            Ok(position)
+        })
+    }
+
+    /// Hides a task from open listings and pickers until `until`, or shows
+    /// it again with `None`.
+    pub fn snooze(&self, id: &str, until: Option<NaiveDate>) -> Result<()> {
+        self.modify("snooze", |doc| {
+            find_task(doc, id)?.start = until;
+            Ok(())
+        })
+    }
+
+    /// Marks an open task as handed off to someone else (`- [>]`), or
+    /// takes it back.
+    pub fn set_waiting(&self, id: &str, waiting: bool) -> Result<()> {
+        self.modify(if waiting { "wait" } else { "resume" }, |doc| {
+            let task = find_task(doc, id)?;
+            if task.done {
+                anyhow::bail!("Task '{}' is already complete", id);
+            }
+
+            task.waiting = waiting;
+            Ok(())
+        })
+    }
+
+    /// Marks a done task open again. A recurring task handed its `every:`
+    /// rule to the instance completing it created right after it; that
+    /// instance is removed and the rule given back, unless work was
+    /// recorded on it or other tasks wait for it.
+    pub fn reopen(&self, id: &str) -> Result<()> {
+        self.modify("reopen", |doc| {
+            let task = find_task(doc, id)?;
+            if !task.done {
+                anyhow::bail!("Task '{}' is not complete", id);
+            }
+
+            let (description, recurring) = (task.description.clone(), task.recurrence.is_some());
+            let next = doc
+                .task_after(id)
+                .filter(|next| {
+                    !recurring
+                        && !next.done
+                        && next.recurrence.is_some()
+                        && next.description == description
+                })
+                .map(|next| (next.id.clone().unwrap_or_default(), next.recurrence, next.sub_items.is_empty()));
+            if let Some((next_id, recurrence, untouched)) = next {
+                let waited_for = todos(doc).iter().any(|t| t.blocked_by.contains(&next_id));
+                if !untouched || waited_for {
+                    anyhow::bail!(
+                        "Cannot reopen '{}': its next occurrence '{}' already has sub-items or blocks other tasks. Complete or delete that one instead",
+                        id,
+                        next_id
+                    );
+                }
+                doc.remove_task(&next_id);
+                find_task(doc, id)?.recurrence = recurrence;
+            }
+
+            find_task(doc, id)?.reopen();
+            Ok(())
+        })
+    }
+
+    /// Moves completed top-level tasks, with all their sub-items and
+    /// subtasks, to the same project in `archive.md`. Tasks with open
+    /// subtasks stay. With `before`, only tasks finished earlier than that
+    /// day are moved. Returns the number of archived tasks.
+    pub fn archive(&self, before: Option<NaiveDate>) -> Result<usize> {
+        self.transaction("archive", |doc, files| {
+            let archivable = |task: &Task| {
+                task.descendants().iter().all(|t| t.done)
+                    && before.is_none_or(|before| {
+                        task.finished_at().is_some_and(|at| at.date() < before)
+                    })
+            };
+
+            let mut taken = Vec::new();
+            for project in &mut doc.projects {
+                let tasks = project.take_tasks(archivable);
+                if !tasks.is_empty() {
+                    taken.push((project.name.clone(), tasks));
+                }
+            }
+            if taken.is_empty() {
+                return Ok(0);
+            }
+
+            let archive_path = self.archive_path();
+            let mut archive = Document::parse(&read_or_empty(&archive_path)?);
+            let mut count = 0;
+            for (name, tasks) in taken {
+                let project = archive.project_or_insert(&name);
+                for task in tasks {
+                    project.push_task(task);
+                    count += 1;
+                }
+            }
+
+            files.move_tasks(archive_path, &archive, true);
+            Ok(count)
+        })
+    }
+
+    pub fn set_priority(&self, id: &str, priority: Option<Priority>) -> Result<()> {
+        self.modify("set_priority", |doc| {
+            find_task(doc, id)?.priority = priority;
+            Ok(())
+        })
+    }
+
+    pub fn print_open_todos(&self, filter: &Filter, today: NaiveDate) -> Result<()> {
+        let todos: Vec<(usize, Todo)> = self
+            .listed_todos()?
+            .into_iter()
+            .filter(|(_, t)| filter.matches(t, today))
+            .collect();
+
+        if todos.is_empty() {
+            println!("No open tasks.");
+            return Ok(());
+        }
+
+        print_numbered(&todos);
+
+        Ok(())
+    }
+
+    pub fn print_snoozed_todos(&self, filter: &Filter, today: NaiveDate) -> Result<()> {
+        let todos: Vec<Todo> = self
+            .snoozed_todos()?
+            .into_iter()
+            .filter(|t| filter.matches(t, today))
+            .collect();
+
+        if todos.is_empty() {
+            println!("No snoozed tasks.");
+            return Ok(());
+        }
+
+        print_grouped(&sort_by_priority(todos));
+
+        Ok(())
+    }
+
+    pub fn print_waiting_todos(&self) -> Result<()> {
+        let todos = self.waiting_todos()?;
+
+        if todos.is_empty() {
+            println!("No tasks waiting on others.");
+            return Ok(());
+        }
+
+        print_grouped(&sort_by_priority(todos));
+
+        Ok(())
+    }
+
+    pub fn print_done_todos(&self, filter: &Filter, today: NaiveDate) -> Result<()> {
+        let todos: Vec<Todo> = self
+            .done_todos()?
+            .into_iter()
+            .filter(|t| filter.matches(t, today))
+            .collect();
+
+        if todos.is_empty() {
+            println!("No completed tasks.");
+            return Ok(());
+        }
+
+        print_grouped(&todos);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::is_valid_id;
+    use tempfile::TempDir;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn store_with_content(dir: &TempDir, content: &str) -> (TodoStore, PathBuf) {
+        let path = dir.path().join("todos.md");
+        fs::write(&path, content).unwrap();
+        (TodoStore::new(path.clone()), path)
+    }
+
+    #[test]
+    fn projects_returns_empty_for_missing_file() {
+        let dir = TempDir::new().unwrap();
+        let store = TodoStore::new(dir.path().join("todos.md"));
+        assert!(store.projects().unwrap().is_empty());
+    }
+
+    #[test]
+    fn projects_lists_all_project_names() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] task\n## Personal\n- [ ] task\n");
+        assert_eq!(store.projects().unwrap(), vec!["Work", "Personal"]);
+    }
+
+    #[test]
+    fn add_project_creates_file_with_header() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("todos.md");
+        let store = TodoStore::new(path.clone());
+
+        store.add_project("Work").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Work\n");
+    }
+
+    #[test]
+    fn add_project_appends_to_existing_file() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task\n");
+
+        store.add_project("Personal").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Work\n- [ ] task\n## Personal\n");
+    }
+
+    #[test]
+    fn add_project_rejects_duplicate() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n");
+
+        let result = store.add_project("Work");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+    }
+
+    #[test]
+    fn delete_project_removes_section() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) =
+            store_with_content(&dir, "## Work\n- [ ] task 1\n## Personal\n- [ ] task 2\n");
+
+        store
+            .delete_project("Work", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Personal\n- [ ] task 2\n");
+    }
+
+    #[test]
+    fn delete_project_removes_last_section() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) =
+            store_with_content(&dir, "## Work\n- [ ] task 1\n## Personal\n- [ ] task 2\n");
+
+        store
+            .delete_project("Personal", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Work\n- [ ] task 1\n");
+    }
+
+    #[test]
+    fn rename_project_rewrites_header_archive_and_trash() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work   \n- [ ] task id:aaaa\n\n## Home\n");
+        fs::write(
+            store.archive_path(),
+            "## Work\n- [x] old id:bbbb\n## Office\n- [x] older id:cccc\n",
+        )
+        .unwrap();
+        fs::write(
+            store.trash_path(),
+            "## Home\n- [ ] gone id:dddd\n  - 🗑 2026-10-17 15:42\n",
+        )
+        .unwrap();
+
+        store.rename_project("Work", "Office").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Office\n- [ ] task id:aaaa\n\n## Home\n"
+        );
+        assert_eq!(
+            fs::read_to_string(store.archive_path()).unwrap(),
+            "## Office\n- [x] older id:cccc\n- [x] old id:bbbb\n"
+        );
+        assert_eq!(
+            fs::read_to_string(store.trash_path()).unwrap(),
+            "## Home\n- [ ] gone id:dddd\n  - 🗑 2026-10-17 15:42\n"
+        );
+    }
+
+    #[test]
+    fn sub_projects_are_addressed_by_path() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] plan id:aaaa\n## Home\n");
+
+        store.add_project("Work/Backend").unwrap();
+        store.add("Work/Backend", "api").unwrap();
+        let result = store.add_project("Office/Ops");
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("'Office' not found"));
+
+        assert_eq!(
+            store.projects().unwrap(),
+            vec!["Work", "Work/Backend", "Home"]
+        );
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("## Work\n- [ ] plan id:aaaa\n### Backend\n- [ ] api id:"));
+        assert_eq!(store.load_all().unwrap()[1].project, "Work/Backend");
+    }
+
+    #[test]
+    fn project_titles_with_slashes_are_kept() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## CI/CD\n- [ ] pipeline\n");
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[0].project, "CI\\/CD");
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("## CI/CD\n- [ ] pipeline id:"));
+
+        store.add_project("CI\\/CD/Deploy").unwrap();
+        store.rename_project("CI\\/CD", "Build\\/Release").unwrap();
+        assert_eq!(
+            store.projects().unwrap(),
+            vec!["Build\\/Release", "Build\\/Release/Deploy"]
+        );
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("## Build/Release\n"));
+        assert!(content.ends_with("### Deploy\n"));
+    }
+
+    #[test]
+    fn archived_projects_are_hidden_from_open_todos() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] plan id:aaaa\n### Backend\n- [ ] api id:bbbb\n## Home\n- [ ] cook id:cccc\n",
+        );
+
+        store
+            .update_project("Work", |meta| meta.status = Some(Status::Archived))
+            .unwrap();
+
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("## Work\n> meta: status:archived\n- [ ] plan"));
+        let open: Vec<String> = store
+            .open_todos()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.description)
+            .collect();
+        assert_eq!(open, vec!["cook"]);
+        assert_eq!(store.load_all().unwrap().len(), 3);
+
+        let result = store.update_project("Office", |_| {});
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn sub_projects_inherit_unset_project_meta() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n> meta: Day job colour:red pomodoro:50\n- [ ] plan id:aaaa\n### Backend\n> meta: APIs pomodoro:30\n- [ ] api id:bbbb\n## Home\n- [ ] cook id:cccc\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[1].project_meta.description, "APIs");
+        assert_eq!(todos[1].project_meta.colour, Some(Colour::Red));
+        assert_eq!(todos[1].project_meta.pomodoro, Some(30));
+
+        assert_eq!(store.target("aaaa").unwrap().minutes, Some(50));
+        assert_eq!(store.target("bbbb").unwrap().minutes, Some(30));
+        assert_eq!(store.target("cccc").unwrap().minutes, None);
+
+        let details = store.project_details().unwrap();
+        assert_eq!(details[1].0, "Work/Backend");
+        assert_eq!(details[1].1.colour, None);
+    }
+
+    #[test]
+    fn filter_by_project_includes_sub_projects() {
+        let filter = Filter {
+            project: Some("Work".to_string()),
+            ..Filter::default()
+        };
+        let today = date(2026, 10, 17);
+        let in_project = |project: &str| Todo {
+            project: project.to_string(),
+            ..todo_with_dates(None, None)
+        };
+
+        assert!(filter.matches(&in_project("Work"), today));
+        assert!(filter.matches(&in_project("Work/Backend"), today));
+        assert!(!filter.matches(&in_project("Workshop"), today));
+        assert!(!filter.matches(&in_project("Home"), today));
+    }
+
+    #[test]
+    fn delete_and_rename_project_include_sub_projects() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] plan id:aaaa\n### Backend\n- [ ] api id:bbbb\n## Home\n- [ ] cook id:cccc\n",
+        );
+
+        store.rename_project("Work", "Office").unwrap();
+        assert_eq!(
+            store.projects().unwrap(),
+            vec!["Office", "Office/Backend", "Home"]
+        );
+        let result = store.rename_project("Office/Backend", "Home/Backend");
+        assert!(result.unwrap_err().to_string().contains("last part"));
+        store
+            .rename_project("Office/Backend", "Office/API")
+            .unwrap();
+
+        store
+            .delete_project("Office", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Home\n- [ ] cook id:cccc\n"
+        );
+        let trashed: Vec<String> = store
+            .trashed()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.project)
+            .collect();
+        assert_eq!(trashed, vec!["Office", "Office/API"]);
+
+        store.restore_project("Office").unwrap();
+        assert_eq!(
+            store.projects().unwrap(),
+            vec!["Home", "Office", "Office/API"]
+        );
+    }
+
+    #[test]
+    fn rename_project_refuses_existing_or_unknown_names() {
+        let dir = TempDir::new().unwrap();
+        let original = "## Work\n## Home\n";
+        let (store, path) = store_with_content(&dir, original);
+
+        let result = store.rename_project("Work", "Home");
+        assert!(result.unwrap_err().to_string().contains("already exists"));
+        let result = store.rename_project("Nowhere", "Else");
+        assert!(result.unwrap_err().to_string().contains("not found"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn delete_project_errors_on_unknown_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n");
+
+        let result = store.delete_project("Unknown", datetime(2026, 10, 17, 15, 42));
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn add_todo_under_correct_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] existing\n## Personal\n");
+
+        let id = store.add("Work", "new task").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "## Work\n- [ ] existing\n- [ ] new task id:{}\n## Personal\n",
+                id
+            )
+        );
+    }
+
+    #[test]
+    fn add_todo_to_last_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n## Personal\n");
+
+        let id = store.add("Personal", "buy milk").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!("## Work\n## Personal\n- [ ] buy milk id:{}\n", id)
+        );
+    }
+
+    #[test]
+    fn add_todo_generates_unique_ids() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n");
+
+        let first = store.add("Work", "first").unwrap();
+        let second = store.add("Work", "second").unwrap();
+
+        assert!(is_valid_id(&first));
+        assert!(is_valid_id(&second));
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn add_todo_errors_on_unknown_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n");
+
+        let result = store.add("Unknown", "task");
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn load_all_returns_todos_with_projects() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] task 1\n- [x] task 2\n## Personal\n- [ ] task 3\n",
+        );
+
+        let todos = store.load_all().unwrap();
+
+        assert_eq!(todos.len(), 3);
+        assert_eq!(todos[0].project, "Work");
+        assert_eq!(todos[0].description, "task 1");
+        assert!(!todos[0].done);
+        assert_eq!(todos[1].project, "Work");
+        assert!(todos[1].done);
+        assert_eq!(todos[2].project, "Personal");
+        assert_eq!(todos[2].description, "task 3");
+    }
+
+    #[test]
+    fn load_all_ignores_todos_without_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "- [ ] orphan\n## Work\n- [ ] task\n");
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].project, "Work");
+    }
+
+    #[test]
+    fn load_all_ignores_pomodoro_lines() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n- [x] done\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos.len(), 2);
+    }
+
+    #[test]
+    fn load_all_returns_empty_for_missing_file() {
+        let dir = TempDir::new().unwrap();
+        let store = TodoStore::new(dir.path().join("todos.md"));
+        assert!(store.load_all().unwrap().is_empty());
+    }
+
+    #[test]
+    fn load_all_reads_ids() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] task id:ab12\n");
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[0].id, "ab12");
+        assert_eq!(todos[0].description, "task");
+    }
+
+    #[test]
+    fn load_all_migrates_todos_without_ids() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] old task\n  - 🍅 2026-02-12 10:00\n- [x] kept id:ab12\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert!(is_valid_id(&todos[0].id));
+        assert_eq!(todos[1].id, "ab12");
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "## Work\n- [ ] old task id:{}\n  - 🍅 2026-02-12 10:00\n- [x] kept id:ab12\n",
+                todos[0].id
+            )
+        );
+    }
+
+    #[test]
+    fn load_all_renumbers_copied_lines() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] standup id:ab12\n- [ ] standup notes id:ab12\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[0].id, "ab12");
+        assert!(is_valid_id(&todos[1].id));
+        assert_ne!(todos[1].id, "ab12");
+
+        store
+            .complete(&todos[1].id, datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("- [ ] standup id:ab12\n"));
+        assert!(content.contains("- [x] standup notes id:"));
+    }
+
+    #[test]
+    fn load_all_does_not_rewrite_migrated_file() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:ab12");
+
+        store.load_all().unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Work\n- [ ] task id:ab12");
+    }
+
+    #[test]
+    fn find_returns_todo_by_id() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) =
+            store_with_content(&dir, "## Work\n- [ ] first id:aaaa\n- [ ] second id:bbbb\n");
+
+        assert_eq!(store.find("bbbb").unwrap().description, "second");
+        assert!(store.find("cccc").is_err());
+    }
+
+    #[test]
+    fn load_all_reads_dates() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] release due:2026-10-20 scheduled:2026-10-18 id:ab12\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[0].description, "release");
+        assert_eq!(todos[0].due, Some(date(2026, 10, 20)));
+        assert_eq!(todos[0].scheduled, Some(date(2026, 10, 18)));
+    }
+
+    #[test]
+    fn add_todo_writes_dates_inline() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n");
+
+        let id = store
+            .add_todo(
+                "Work",
+                &NewTodo {
+                    description: "release".to_string(),
+                    due: Some(date(2026, 10, 20)),
+                    scheduled: Some(date(2026, 10, 18)),
+                    ..NewTodo::default()
+                },
+            )
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "## Work\n- [ ] release due:2026-10-20 scheduled:2026-10-18 id:{}\n",
+                id
+            )
+        );
+    }
+
+    fn todo_with_dates(due: Option<NaiveDate>, scheduled: Option<NaiveDate>) -> Todo {
+        Todo {
+            id: "ab12".to_string(),
+            description: "task".to_string(),
+            project: "Work".to_string(),
+            due,
+            scheduled,
+            ..Todo::default()
+        }
+    }
+
+    #[test]
+    fn filter_overdue_matches_past_due_dates_only() {
+        let filter = Filter {
+            date: Some(DateFilter::Overdue),
+            ..Filter::default()
+        };
+        let today = date(2026, 10, 17);
+
+        assert!(filter.matches(&todo_with_dates(Some(date(2026, 10, 16)), None), today));
+        assert!(!filter.matches(&todo_with_dates(Some(today), None), today));
+        assert!(!filter.matches(&todo_with_dates(None, Some(date(2026, 10, 1))), today));
+    }
+
+    #[test]
+    fn filter_today_includes_scheduled_and_overdue() {
+        let filter = Filter {
+            date: Some(DateFilter::Today),
+            ..Filter::default()
+        };
+        let today = date(2026, 10, 17);
+
+        assert!(filter.matches(&todo_with_dates(None, Some(today)), today));
+        assert!(filter.matches(&todo_with_dates(Some(date(2026, 10, 1)), None), today));
+        assert!(!filter.matches(&todo_with_dates(Some(date(2026, 10, 18)), None), today));
+        assert!(!filter.matches(&todo_with_dates(None, None), today));
+    }
+
+    #[test]
+    fn filter_week_covers_next_seven_days() {
+        let filter = Filter {
+            date: Some(DateFilter::Week),
+            ..Filter::default()
+        };
+        let today = date(2026, 10, 17);
+
+        assert!(filter.matches(&todo_with_dates(Some(date(2026, 10, 23)), None), today));
+        assert!(!filter.matches(&todo_with_dates(Some(date(2026, 10, 24)), None), today));
+    }
+
+    #[test]
+    fn filter_since_matches_tasks_finished_from_that_day() {
+        let filter = Filter {
+            since: Some(date(2026, 10, 15)),
+            ..Filter::default()
+        };
+        let today = date(2026, 10, 17);
+        let finished_on = |day| Todo {
+            finished: day,
+            ..todo_with_dates(None, None)
+        };
+
+        assert!(filter.matches(&finished_on(Some(date(2026, 10, 15))), today));
+        assert!(!filter.matches(&finished_on(Some(date(2026, 10, 14))), today));
+        assert!(!filter.matches(&finished_on(None), today));
+    }
+
+    #[test]
+    fn load_all_reads_completion_time() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [x] done id:aaaa\n  - 🍅 2026-10-16 09:00\n  - ✅ 2026-10-17 15:42\n",
+        );
+
+        let todo = store.find("aaaa").unwrap();
+        assert_eq!(todo.finished, Some(date(2026, 10, 17)));
+        assert_eq!(todo.to_string(), "done (aaaa) [done 2026-10-17]");
+    }
+
+    #[test]
+    fn load_all_dates_done_tasks_by_latest_pomodoro() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [x] done id:aaaa\n  - 🍅 2026-10-16 09:00\n  - 🍅 2026-10-12 09:00\n- [ ] open id:bbbb\n  - 🍅 2026-10-16 09:00\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[0].finished, Some(date(2026, 10, 16)));
+        assert_eq!(todos[1].finished, None);
+    }
+
+    #[test]
+    fn default_filter_matches_everything() {
+        assert!(Filter::default().matches(&todo_with_dates(None, None), date(2026, 10, 17)));
+    }
+
+    #[test]
+    fn display_includes_id_and_dates() {
+        let todo = todo_with_dates(Some(date(2026, 10, 20)), None);
+        assert_eq!(todo.to_string(), "task (ab12) [due 2026-10-20]");
+    }
+
+    #[test]
+    fn load_all_reads_tags_and_contexts() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] patch #infra #security servers @office id:ab12\n",
+        );
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[0].tags, vec!["infra", "security"]);
+        assert_eq!(todos[0].contexts, vec!["office"]);
+        assert_eq!(
+            todos[0].description,
+            "patch #infra #security servers @office"
+        );
+    }
+
+    fn labelled(tags: &[&str], contexts: &[&str]) -> Todo {
+        Todo {
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            contexts: contexts.iter().map(|c| c.to_string()).collect(),
+            ..todo_with_dates(None, None)
+        }
+    }
+
+    #[test]
+    fn filter_requires_all_tags_and_contexts() {
+        let filter = Filter {
+            tags: vec!["infra".to_string()],
+            contexts: vec!["Office".to_string()],
+            ..Filter::default()
+        };
+        let today = date(2026, 10, 17);
+
+        assert!(filter.matches(&labelled(&["Infra", "ops"], &["office"]), today));
+        assert!(!filter.matches(&labelled(&["infra"], &[]), today));
+        assert!(!filter.matches(&labelled(&[], &["office"]), today));
+    }
+
+    #[test]
+    fn label_counts_groups_case_insensitively() {
+        let todos = vec![
+            labelled(&["infra"], &["office"]),
+            labelled(&["Infra", "ci"], &[]),
+            labelled(&[], &["office", "home"]),
+        ];
+
+        assert_eq!(
+            label_counts(&todos),
+            vec![
+                ("#ci".to_string(), 1),
+                ("#infra".to_string(), 2),
+                ("@home".to_string(), 1),
+                ("@office".to_string(), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn display_prefixes_priority() {
+        let mut todo = todo_with_dates(None, None);
+        todo.priority = Some(Priority::B);
+        assert_eq!(todo.to_string(), "(B) task (ab12)");
+    }
+
+    fn todo_in(project: &str, description: &str, priority: Option<Priority>) -> Todo {
+        Todo {
+            description: description.to_string(),
+            project: project.to_string(),
+            priority,
+            ..todo_with_dates(None, None)
+        }
+    }
+
+    #[test]
+    fn sort_by_priority_sorts_within_projects() {
+        let todos = vec![
+            todo_in("Work", "none", None),
+            todo_in("Work", "c", Some(Priority::C)),
+            todo_in("Work", "a", Some(Priority::A)),
+            todo_in("Personal", "b", Some(Priority::B)),
+            todo_in("Personal", "first a", Some(Priority::A)),
+            todo_in("Personal", "second a", Some(Priority::A)),
+        ];
+
+        let todos = sort_by_priority(todos);
+
+        let order: Vec<&str> = todos.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(order, vec!["a", "c", "none", "first a", "second a", "b"]);
+    }
+
+    fn subtask_of(description: &str, parent: &str, priority: Option<Priority>) -> Todo {
+        Todo {
+            parent: Some(parent.to_string()),
+            depth: 1,
+            ..todo_in("Work", description, priority)
+        }
+    }
+
+    #[test]
+    fn sort_by_priority_keeps_subtasks_under_their_parent() {
+        let todos = vec![
+            todo_in("Work", "parent", None),
+            subtask_of("low", "p", Some(Priority::C)),
+            subtask_of("high", "p", Some(Priority::A)),
+            todo_in("Work", "urgent", Some(Priority::A)),
+        ];
+
+        let todos = sort_by_priority(todos);
+
+        let order: Vec<&str> = todos.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(order, vec!["urgent", "parent", "high", "low"]);
+    }
+
+    #[test]
+    fn display_shows_subtask_progress() {
+        let mut todo = todo_with_dates(None, None);
+        todo.progress = Some((2, 5));
+        assert_eq!(todo.to_string(), "task (ab12) [2/5]");
+    }
+
+    #[test]
+    fn load_all_returns_subtasks_as_tree() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] parent id:aaaa\n  - [x] one id:bbbb\n  - [ ] two id:cccc\n    - [ ] deep id:dddd\n",
+        );
+
+        let todos = store.load_all().unwrap();
+
+        let tree: Vec<(&str, usize, Option<&str>)> = todos
+            .iter()
+            .map(|t| (t.id.as_str(), t.depth, t.parent.as_deref()))
+            .collect();
+        assert_eq!(
+            tree,
+            vec![
+                ("aaaa", 0, None),
+                ("bbbb", 1, Some("aaaa")),
+                ("cccc", 1, Some("aaaa")),
+                ("dddd", 2, Some("cccc")),
+            ]
+        );
+        assert_eq!(todos[0].progress, Some((1, 2)));
+        assert_eq!(todos[1].progress, None);
+    }
+
+    #[test]
+    fn add_subtask_nests_under_parent() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] parent id:aaaa\n  - 📝 plan\n- [ ] other id:bbbb\n",
+        );
+
+        let id = store
+            .add_subtask(
+                "aaaa",
+                &NewTodo {
+                    description: "step".to_string(),
+                    ..NewTodo::default()
+                },
+            )
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "## Work\n- [ ] parent id:aaaa\n  - 📝 plan\n  - [ ] step id:{}\n- [ ] other id:bbbb\n",
+                id
+            )
+        );
+    }
+
+    #[test]
+    fn add_subtask_errors_on_unknown_parent() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n");
+
+        let result = store.add_subtask("zzzz", &NewTodo::default());
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn complete_last_subtask_reports_parent() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] parent id:aaaa\n  - [x] one id:bbbb\n  - [ ] two id:cccc\n  - [ ] three id:dddd\n",
+        );
+
+        let completion = store
+            .complete("cccc", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert!(completion.parent.is_none());
+
+        let completion = store
+            .complete("dddd", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        let parent = completion.parent.unwrap();
+        assert_eq!(parent.id, "aaaa");
+        assert_eq!(parent.progress, Some((3, 3)));
+        assert!(!store.find("aaaa").unwrap().done);
+    }
+
+    #[test]
+    fn snoozed_tasks_are_hidden_until_their_start_date() {
+        let dir = TempDir::new().unwrap();
+        let today = Local::now().date_naive();
+        let (store, path) = store_with_content(
+            &dir,
+            &format!(
+                "## Work\n- [ ] now id:aaaa\n- [ ] later id:bbbb\n  - [ ] step id:cccc\n- [ ] due start:{} id:dddd\n",
+                format_date(today)
+            ),
+        );
+
+        store.snooze("bbbb", Some(today + Days::new(3))).unwrap();
+
+        assert!(fs::read_to_string(&path).unwrap().contains(&format!(
+            "- [ ] later start:{} id:bbbb",
+            format_date(today + Days::new(3))
+        )));
+        let ids = |todos: Vec<Todo>| todos.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids(store.open_todos().unwrap()), vec!["aaaa", "dddd"]);
+        let listed: Vec<usize> = store
+            .listed_todos()
+            .unwrap()
+            .iter()
+            .map(|(n, _)| *n)
+            .collect();
+        assert_eq!(listed, vec![1, 2]);
+        assert_eq!(ids(store.snoozed_todos().unwrap()), vec!["bbbb", "cccc"]);
+        assert!(store
+            .find("cccc")
+            .unwrap()
+            .to_string()
+            .contains("[snoozed until"));
+
+        store.snooze("bbbb", None).unwrap();
+        assert_eq!(store.open_todos().unwrap().len(), 4);
+        assert!(store.snoozed_todos().unwrap().is_empty());
+    }
+
+    #[test]
+    fn estimates_show_pomodoro_progress() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] spec est:4🍅 id:aaaa\n  - 🍅 2026-10-17 10:00\n  - 🍅 2026-10-17 11:00 cancelled\n- [ ] plain id:bbbb\n  - 🍅 2026-10-17 12:00\n",
+        );
+
+        let spec = store.find("aaaa").unwrap();
+        assert_eq!(spec.estimate, Some(4));
+        assert_eq!(spec.pomodoros, (1, 1));
+        assert_eq!(spec.to_string(), "spec (aaaa) [🍅 1/4, 1 cancelled]");
+        assert_eq!(store.find("bbbb").unwrap().to_string(), "plain (bbbb)");
+
+        let id = store
+            .add_todo(
+                "Work",
+                &NewTodo {
+                    description: "tests".to_string(),
+                    estimate: Some(2),
+                    ..NewTodo::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(store.text(&id).unwrap(), "tests est:2🍅");
+    }
+
+    #[test]
+    fn search_matches_descriptions_and_notes_with_open_indexes() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] write spec id:aaaa\n- [ ] Release v2 pri:A id:bbbb\n  - 📝 draft the notes\n  - 📝 unrelated\n- [x] release v1 id:cccc\n",
+        );
+
+        let results = store.search("RELEASE").unwrap();
+        let found: Vec<(&str, Option<usize>)> = results
+            .matches
+            .iter()
+            .map(|m| (m.todo.id.as_str(), m.index))
+            .collect();
+        // (A) sorts first in the open list
+        assert_eq!(found, vec![("bbbb", Some(1)), ("cccc", None)]);
+
+        let results = store.search("release notes").unwrap();
+        assert_eq!(results.matches.len(), 1);
+        assert_eq!(results.matches[0].notes, vec!["draft the notes"]);
+
+        assert!(store.search("spec release").unwrap().is_empty());
+        assert!(store.search("  ").is_err());
+    }
+
+    #[test]
+    fn listed_numbers_match_across_list_pickers_and_search() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] alpha id:aaaa\n- [>] gamma id:bbbb\n- [ ] weekly review pri:A id:cccc\n- [ ] beta #home id:dddd\n",
+        );
+
+        let listed: Vec<(usize, String)> = store
+            .listed_todos()
+            .unwrap()
+            .into_iter()
+            .map(|(n, t)| (n, t.id))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                (1, "cccc".to_string()),
+                (2, "aaaa".to_string()),
+                (3, "dddd".to_string())
+            ]
+        );
+
+        // Searching, like filtering the list, keeps the numbers
+        let results = store.search("beta").unwrap();
+        assert_eq!(results.matches[0].index, Some(3));
+        assert_eq!(store.search("gamma").unwrap().matches[0].index, None);
+    }
+
+    #[test]
+    fn search_covers_project_names_and_the_archive() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Home\n- [ ] paint id:aaaa\n### Garden\n");
+        fs::write(store.archive_path(), "## Home\n- [x] paint fence id:bbbb\n").unwrap();
+
+        let results = store.search("paint").unwrap();
+        let archived: Vec<(&str, bool)> = results
+            .matches
+            .iter()
+            .map(|m| (m.todo.id.as_str(), m.archived))
+            .collect();
+        assert_eq!(archived, vec![("aaaa", false), ("bbbb", true)]);
+        assert!(results.projects.is_empty());
+
+        let results = store.search("garden").unwrap();
+        assert_eq!(results.projects, vec!["Home/Garden"]);
+        assert!(results.matches.is_empty());
+    }
+
+    #[test]
+    fn estimated_todos_include_the_archive() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [x] spec est:2🍅 id:aaaa\n  - 🍅 2026-10-17 10:00\n- [ ] open est:1🍅 id:bbbb\n- [x] plain id:cccc\n",
+        );
+        fs::write(
+            store.archive_path(),
+            "## Home\n- [x] paint est:3🍅 id:dddd\n  - 🍅 2026-10-10 10:00\n",
+        )
+        .unwrap();
+
+        let todos = store.estimated_todos().unwrap();
+        let summary: Vec<(&str, Option<usize>, usize)> = todos
+            .iter()
+            .map(|t| (t.id.as_str(), t.estimate, t.pomodoros.0))
+            .collect();
+        assert_eq!(summary, vec![("aaaa", Some(2), 1), ("dddd", Some(3), 1)]);
+        assert_eq!(todos[1].project, "Home");
+        assert_eq!(comparison(5, 2), "5 estimated, 2 done (40%)");
+    }
+
+    #[test]
+    fn waiting_tasks_are_open_but_not_actionable() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] write id:aaaa\n- [>] review PR @alice id:bbbb\n- [x] plan id:cccc\n",
+        );
+
+        let review = store.find("bbbb").unwrap();
+        assert!(review.waiting && !review.done);
+        assert!(review.to_string().ends_with("[waiting]"));
+        let ids = |todos: Vec<Todo>| todos.into_iter().map(|t| t.id).collect::<Vec<_>>();
+        assert_eq!(ids(store.open_todos().unwrap()), vec!["aaaa", "bbbb"]);
+        assert_eq!(ids(store.waiting_todos().unwrap()), vec!["bbbb"]);
+        let listed: Vec<(usize, String)> = store
+            .listed_todos()
+            .unwrap()
+            .into_iter()
+            .map(|(n, t)| (n, t.id))
+            .collect();
+        assert_eq!(listed, vec![(1, "aaaa".to_string())]);
+
+        store.set_waiting("aaaa", true).unwrap();
+        store.set_waiting("bbbb", false).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Work\n- [>] write id:aaaa\n- [ ] review PR @alice id:bbbb\n- [x] plan id:cccc\n"
+        );
+
+        let result = store.set_waiting("cccc", true);
+        assert!(result.unwrap_err().to_string().contains("already complete"));
+    }
+
+    #[test]
+    fn blocked_tasks_wait_for_open_blockers() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] write spec id:aaaa\n- [ ] review spec id:bbbb\n- [ ] deploy id:cccc\n",
+        );
+
+        assert_eq!(
+            store.block("cccc", "aaaa").unwrap().description,
+            "write spec"
+        );
+        assert_eq!(store.block("cccc", "Review").unwrap().id, "bbbb");
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("- [ ] deploy blocked:aaaa blocked:bbbb id:cccc"));
+
+        let deploy = store.find("cccc").unwrap();
+        assert_eq!(deploy.blocked_by, vec!["aaaa", "bbbb"]);
+        assert!(!deploy.is_actionable());
+        assert!(deploy.to_string().ends_with("[blocked by aaaa, bbbb]"));
+        let actionable: Vec<String> = store
+            .listed_todos()
+            .unwrap()
+            .into_iter()
+            .filter(|(_, t)| t.is_actionable())
+            .map(|(_, t)| t.id)
+            .collect();
+        assert_eq!(actionable, vec!["aaaa", "bbbb"]);
+
+        let completion = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert!(completion.unblocked.is_empty());
+        let completion = store
+            .complete("bbbb", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        let unblocked: Vec<String> = completion.unblocked.into_iter().map(|t| t.id).collect();
+        assert_eq!(unblocked, vec!["cccc"]);
+        assert!(store.find("cccc").unwrap().is_actionable());
+    }
+
+    #[test]
+    fn block_rejects_cycles_and_unclear_references() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] spec id:aaaa\n- [ ] spec review id:bbbb\n- [ ] deploy id:cccc\n",
+        );
+
+        store.block("bbbb", "spec").unwrap();
+        store.block("cccc", "bbbb").unwrap();
+
+        let error = |result: Result<Todo>| result.unwrap_err().to_string();
+        assert!(error(store.block("aaaa", "aaaa")).contains("cannot block itself"));
+        assert!(error(store.block("aaaa", "deploy")).contains("already waits"));
+        assert!(error(store.block("cccc", "ec")).contains("matches several tasks"));
+        assert!(error(store.block("cccc", "release")).contains("No task matches"));
+    }
+
+    #[test]
+    fn unblock_removes_one_or_all_dependencies() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] a id:aaaa\n- [ ] b id:bbbb\n- [ ] c blocked:aaaa blocked:bbbb blocked:zzzz id:cccc\n",
+        );
+
+        store.unblock("cccc", Some("a")).unwrap();
+        assert_eq!(store.text("cccc").unwrap(), "c blocked:bbbb blocked:zzzz");
+        store.unblock("cccc", Some("zzzz")).unwrap();
+        let result = store.unblock("cccc", Some("aaaa"));
+        assert!(result.unwrap_err().to_string().contains("not blocked by"));
+
+        store.unblock("cccc", None).unwrap();
+        assert_eq!(store.text("cccc").unwrap(), "c");
+    }
+
+    #[test]
+    fn add_todo_resolves_blockers() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] write spec id:aaaa\n");
+
+        let id = store
+            .add_todo(
+                "Work",
+                &NewTodo {
+                    description: "deploy".to_string(),
+                    blocked_by: vec!["write spec".to_string()],
+                    ..NewTodo::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(store.find(&id).unwrap().blocked_by, vec!["aaaa"]);
+    }
+
+    #[test]
+    fn complete_recurring_subtask_stays_nested() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] parent id:aaaa\n  - [ ] weekly every:week id:bbbb\n",
+        );
+
+        let completion = store
+            .complete("bbbb", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        let next = completion.next.unwrap();
+        assert_eq!(next.parent.as_deref(), Some("aaaa"));
+        assert!(completion.parent.is_none());
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            format!(
+                "## Work\n- [ ] parent id:aaaa\n  - [x] weekly id:bbbb\n    - ✅ 2026-10-17 15:42\n  - [ ] weekly due:2026-10-24 every:week id:{}\n",
+                next.id
+            )
+        );
+    }
+
+    #[test]
+    fn add_pomodoro_to_subtask_indents_below_it() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] parent id:aaaa\n  - [ ] child id:bbbb\n",
+        );
+
+        store
+            .add_pomodoro(
+                &store.target("bbbb").unwrap(),
+                datetime(2026, 10, 17, 9, 0),
+                false,
+            )
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] parent id:aaaa\n  - [ ] child id:bbbb\n    - 🍅 2026-10-17 09:00\n"
+        );
+    }
+
+    #[test]
+    fn add_todo_writes_priority() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n");
+
+        let id = store
+            .add_todo(
+                "Work",
+                &NewTodo {
+                    description: "ship".to_string(),
+                    priority: Some(Priority::A),
+                    ..NewTodo::default()
+                },
+            )
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, format!("## Work\n- [ ] ship pri:A id:{}\n", id));
+    }
+
+    #[test]
+    fn set_priority_updates_and_clears() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] ship id:aaaa\n  - 📝 note\n- [ ] other id:bbbb\n",
+        );
+
+        store.set_priority("aaaa", Some(Priority::C)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Work\n- [ ] ship pri:C id:aaaa\n  - 📝 note\n- [ ] other id:bbbb\n"
+        );
+
+        store.set_priority("aaaa", None).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Work\n- [ ] ship id:aaaa\n  - 📝 note\n- [ ] other id:bbbb\n"
+        );
+    }
+
+    #[test]
+    fn edit_rewrites_line_and_keeps_sub_items() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] shp v2 pri:B id:aaaa\n  - 🍅 2026-10-17 10:00\n  - [ ] sub id:bbbb\n- [ ] other id:cccc\n",
+        );
+        assert_eq!(store.text("aaaa").unwrap(), "shp v2 pri:B");
+
+        store
+            .edit("aaaa", "ship v2 #release due:2026-10-20")
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] ship v2 #release due:2026-10-20 id:aaaa\n  - 🍅 2026-10-17 10:00\n  - [ ] sub id:bbbb\n- [ ] other id:cccc\n"
+        );
+    }
+
+    #[test]
+    fn edit_rejects_empty_text() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
+
+        let result = store.edit("aaaa", "  ");
+        assert!(result.unwrap_err().to_string().contains("empty"));
+    }
+
+    #[test]
+    fn edit_errors_on_unknown_id() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
+
+        let result = store.edit("zzzz", "new");
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn move_task_takes_sub_items_to_other_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-10-17 10:00\n  - [ ] sub id:bbbb\n- [ ] other id:cccc\n\n## Home\n- [ ] chores id:dddd\n",
+        );
+
+        store.move_task("aaaa", "Home").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] other id:cccc\n\n## Home\n- [ ] chores id:dddd\n- [ ] task id:aaaa\n  - 🍅 2026-10-17 10:00\n  - [ ] sub id:bbbb\n"
+        );
+        assert_eq!(store.find("bbbb").unwrap().project, "Home");
+    }
+
+    #[test]
+    fn move_task_errors_on_unknown_project_without_changes() {
+        let dir = TempDir::new().unwrap();
+        let content = "## Work\n- [ ] task id:aaaa\n";
+        let (store, path) = store_with_content(&dir, content);
+
+        let result = store.move_task("aaaa", "Nowhere");
+
+        assert!(result.unwrap_err().to_string().contains("not found"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn move_task_errors_on_unknown_id() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n## Home\n");
+
+        let result = store.move_task("zzzz", "Home");
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Task 'zzzz' not found"));
+    }
+
+    #[test]
+    fn reorder_counts_listed_positions_and_respects_priorities() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] a pri:A id:aaaa\n- [ ] snoozed start:2099-01-01 id:ssss\n- [>] waiting id:wwww\n- [ ] b id:bbbb\n- [ ] c id:cccc\n",
+        );
+        let listed = || -> Vec<String> {
+            store
+                .listed_todos()
+                .unwrap()
+                .into_iter()
+                .map(|(_, t)| t.id)
+                .collect()
+        };
+
+        let result = store.reorder("cccc", 0);
+        assert!(result.unwrap_err().to_string().contains("higher priority"));
+        let result = store.reorder("aaaa", usize::MAX);
+        assert!(result.unwrap_err().to_string().contains("lower priority"));
+        assert_eq!(listed(), vec!["aaaa", "bbbb", "cccc"]);
+
+        assert_eq!(store.reorder("cccc", 1).unwrap(), 1);
+        assert_eq!(listed(), vec!["aaaa", "cccc", "bbbb"]);
+        assert_eq!(store.reorder("cccc", usize::MAX).unwrap(), 2);
+        assert_eq!(listed(), vec!["aaaa", "bbbb", "cccc"]);
+
+        let result = store.reorder("ssss", 0);
+        assert!(result.unwrap_err().to_string().contains("not listed"));
+    }
+
+    #[test]
+    fn reorder_changes_listing_order() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] a id:aaaa\n- [ ] b id:bbbb\n- [ ] c id:cccc\n## Home\n- [ ] d id:dddd\n",
+        );
+
+        assert_eq!(store.reorder("cccc", 0).unwrap(), 0);
+        assert_eq!(store.reorder("aaaa", 1).unwrap(), 1);
+        store.reorder_project("Home", 0).unwrap();
+
+        let ids: Vec<String> = store
+            .open_todos()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, vec!["dddd", "cccc", "aaaa", "bbbb"]);
+
+        let result = store.reorder("zzzz", 0);
+        assert!(result.unwrap_err().to_string().contains("'zzzz' not found"));
+        let result = store.reorder_project("Nowhere", 0);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("'Nowhere' not found"));
+    }
+
+    #[test]
+    fn reopen_marks_done_task_open() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [x] task id:aaaa\n  - 🍅 2026-10-17 10:00\n",
+        );
+
+        store.reopen("aaaa").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-10-17 10:00\n"
+        );
+    }
+
+    #[test]
+    fn reopen_drops_completion_time() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
+
+        store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert_eq!(
+            store.find("aaaa").unwrap().finished,
+            Some(date(2026, 10, 17))
+        );
+        store.reopen("aaaa").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "## Work\n- [ ] task id:aaaa\n");
+    }
+
+    #[test]
+    fn reopen_recurring_task_takes_back_its_next_occurrence() {
+        let dir = TempDir::new().unwrap();
+        let content =
+            "## Home\n- [ ] water plants due:2026-10-17 every:week id:aaaa\n- [ ] other id:bbbb\n";
+        let (store, path) = store_with_content(&dir, content);
+
+        let next = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap()
+            .next
+            .unwrap();
+        store.reopen("aaaa").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+        assert!(store.find(&next.id).is_err());
+    }
+
+    #[test]
+    fn reopen_refuses_when_next_occurrence_has_work() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) =
+            store_with_content(&dir, "## Home\n- [ ] water plants every:week id:aaaa\n");
+
+        let next = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap()
+            .next
+            .unwrap();
+        store.add_note(&next.id, "used the new can").unwrap();
+        let before = fs::read_to_string(&path).unwrap();
+
+        let result = store.reopen("aaaa");
+        assert!(result.unwrap_err().to_string().contains("next occurrence"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), before);
+    }
+
+    #[test]
+    fn reopen_errors_on_open_task() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
+
+        let result = store.reopen("aaaa");
+        assert!(result.unwrap_err().to_string().contains("not complete"));
+    }
+
+    #[test]
+    fn done_todos_returns_only_completed() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) =
+            store_with_content(&dir, "## Work\n- [x] done id:aaaa\n- [ ] open id:bbbb\n");
+
+        let done = store.done_todos().unwrap();
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].id, "aaaa");
+    }
+
+    #[test]
+    fn archive_moves_done_tasks_under_their_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [x] shipped id:aaaa\n  - 🍅 2026-10-01 10:00\n  - [x] sub id:bbbb\n- [ ] open id:cccc\n  - [x] done sub id:dddd\n- [x] has open sub id:eeee\n  - [ ] open sub id:ffff\n\n## Home\n- [x] dishes id:gggg\n",
+        );
+
+        assert_eq!(store.archive(None).unwrap(), 2);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] open id:cccc\n  - [x] done sub id:dddd\n- [x] has open sub id:eeee\n  - [ ] open sub id:ffff\n\n## Home\n"
+        );
+        let archive = fs::read_to_string(store.archive_path()).unwrap();
+        assert_eq!(
+            archive,
+            "## Work\n- [x] shipped id:aaaa\n  - 🍅 2026-10-01 10:00\n  - [x] sub id:bbbb\n## Home\n- [x] dishes id:gggg\n"
+        );
+    }
+
+    #[test]
+    fn archive_appends_to_existing_archive() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [x] new id:bbbb\n");
+        fs::write(store.archive_path(), "## Work\n- [x] old id:aaaa\n").unwrap();
+
+        store.archive(None).unwrap();
+
+        let archive = fs::read_to_string(store.archive_path()).unwrap();
+        assert_eq!(archive, "## Work\n- [x] old id:aaaa\n- [x] new id:bbbb\n");
+    }
+
+    #[test]
+    fn archive_older_than_keeps_recent_and_undated_tasks() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [x] old id:aaaa\n  - ✅ 2026-10-01 09:00\n- [x] recent id:bbbb\n  - ✅ 2026-10-10 09:00\n- [x] undated id:cccc\n",
+        );
+
+        assert_eq!(store.archive(Some(date(2026, 10, 3))).unwrap(), 1);
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [x] recent id:bbbb\n  - ✅ 2026-10-10 09:00\n- [x] undated id:cccc\n"
+        );
     }
 
     #[test]
-    fn delete_project_removes_section() {
+    fn archive_without_done_tasks_writes_nothing() {
         let dir = TempDir::new().unwrap();
-        let (store, path) =
-            store_with_content(&dir, "## Work\n- [ ] task 1\n## Personal\n- [ ] task 2\n");
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] open id:aaaa\n");
 
-        store.delete_project("Work").unwrap();
-
-        let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Personal\n- [ ] task 2\n");
+        assert_eq!(store.archive(None).unwrap(), 0);
+        assert!(!store.archive_path().exists());
     }
 
     #[test]
-    fn delete_project_removes_last_section() {
+    fn mutations_leave_no_temp_files() {
         let dir = TempDir::new().unwrap();
-        let (store, path) =
-            store_with_content(&dir, "## Work\n- [ ] task 1\n## Personal\n- [ ] task 2\n");
+        let (store, _) = store_with_content(&dir, "## Work\n");
 
-        store.delete_project("Personal").unwrap();
+        store.add("Work", "task").unwrap();
+        assert!(store.add("Nowhere", "task").is_err());
 
-        let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Work\n- [ ] task 1\n");
+        let names: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 3);
+        assert!(names.contains(&"todos.md".to_string()));
+        assert!(names.contains(&"journal.jsonl".to_string()));
+        assert!(names.contains(&"todos.md.lock".to_string()));
+        assert!(FileLock::acquire(&dir.path().join("todos.md")).is_ok());
     }
 
     #[test]
-    fn delete_project_errors_on_unknown_project() {
+    fn failed_writes_keep_moved_tasks_and_are_not_journaled() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n");
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
+        store
+            .delete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        let journaled = store.journal().undoable().unwrap().len();
 
-        let result = store.delete_project("Unknown");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("not found"));
+        // Writing todos.md fails while its temporary file cannot be created
+        let blocker = dir.path().join(".todos.md.tmp");
+        fs::create_dir(&blocker).unwrap();
+        assert!(store.restore("aaaa").is_err());
+        fs::remove_dir(&blocker).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "## Work\n");
+        assert_eq!(store.trashed().unwrap().len(), 1);
+        assert_eq!(store.journal().undoable().unwrap().len(), journaled);
     }
 
     #[test]
-    fn add_todo_under_correct_project() {
+    fn undo_reverts_the_last_operations() {
         let dir = TempDir::new().unwrap();
-        let (store, path) = store_with_content(&dir, "## Work\n- [ ] existing\n## Personal\n");
+        let original = "## Work\n- [ ] first id:aaaa\n\n## Home\n- [ ] second id:bbbb\n";
+        let (store, path) = store_with_content(&dir, original);
 
-        store.add("Work", "new task").unwrap();
+        store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        store.add_note("bbbb", "remember").unwrap();
+        store
+            .delete_project("Home", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
 
-        let content = fs::read_to_string(&path).unwrap();
+        let undone = store.undo(2).unwrap();
+        let ops: Vec<&str> = undone.iter().map(|e| e.op.as_str()).collect();
+        assert_eq!(ops, vec!["delete_project", "add_note"]);
         assert_eq!(
-            content,
-            "## Work\n- [ ] existing\n- [ ] new task\n## Personal\n"
+            fs::read_to_string(&path).unwrap(),
+            "## Work\n- [x] first id:aaaa\n  - ✅ 2026-10-17 15:42\n\n## Home\n- [ ] second id:bbbb\n"
         );
+
+        // Undoing is not itself undone: the next undo goes further back
+        assert_eq!(store.undo(5).unwrap().len(), 1);
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(store.undo(1).unwrap().is_empty());
     }
 
     #[test]
-    fn add_todo_to_last_project() {
+    fn undo_survives_unrelated_edits() {
         let dir = TempDir::new().unwrap();
-        let (store, path) = store_with_content(&dir, "## Work\n## Personal\n");
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
 
-        store.add("Personal", "buy milk").unwrap();
+        store.add_note("aaaa", "remember").unwrap();
+        fs::write(
+            &path,
+            "# My todos\n\n## Work\n- [ ] task id:aaaa\n  - 📝 remember\n",
+        )
+        .unwrap();
+        store.undo(1).unwrap();
 
-        let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Work\n## Personal\n- [ ] buy milk\n");
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# My todos\n\n## Work\n- [ ] task id:aaaa\n"
+        );
     }
 
     #[test]
-    fn add_todo_errors_on_unknown_project() {
+    fn undo_reverts_id_migrations_of_hand_added_tasks() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n");
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
 
-        let result = store.add("Unknown", "task");
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("not found"));
+        store.set_priority("aaaa", Some(Priority::A)).unwrap();
+        fs::write(&path, "## Work\n- [ ] task pri:A id:aaaa\n- [ ] by hand\n").unwrap();
+        store.load_all().unwrap();
+        assert!(!fs::read_to_string(&path)
+            .unwrap()
+            .contains("- [ ] by hand\n"));
+
+        let undone = store.undo(1).unwrap();
+        let ops: Vec<&str> = undone.iter().map(|e| e.op.as_str()).collect();
+        assert_eq!(ops, vec![ASSIGN_IDS, "set_priority"]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Work\n- [ ] task id:aaaa\n- [ ] by hand\n"
+        );
+
+        // A migration alone is nothing to undo
+        store.load_all().unwrap();
+        assert!(store.undo(1).unwrap().is_empty());
     }
 
     #[test]
-    fn load_all_returns_todos_with_projects() {
+    fn undo_refuses_when_changed_lines_were_edited() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(
-            &dir,
-            "## Work\n- [ ] task 1\n- [x] task 2\n## Personal\n- [ ] task 3\n",
-        );
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
 
-        let todos = store.load_all().unwrap();
+        store.set_priority("aaaa", Some(Priority::A)).unwrap();
+        let edited = "## Work\n- [ ] renamed pri:A id:aaaa\n";
+        fs::write(&path, edited).unwrap();
 
-        assert_eq!(todos.len(), 3);
-        assert_eq!(todos[0].project, "Work");
-        assert_eq!(todos[0].description, "task 1");
-        assert!(!todos[0].done);
-        assert_eq!(todos[1].project, "Work");
-        assert!(todos[1].done);
-        assert_eq!(todos[2].project, "Personal");
-        assert_eq!(todos[2].description, "task 3");
+        let result = store.undo(1);
+        assert!(result.unwrap_err().to_string().contains("edited since"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
     }
 
     #[test]
-    fn load_all_ignores_todos_without_project() {
+    fn undo_restores_archived_tasks() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "- [ ] orphan\n## Work\n- [ ] task\n");
+        let original = "## Work\n- [x] done id:aaaa\n- [ ] open id:bbbb\n";
+        let (store, path) = store_with_content(&dir, original);
+        fs::write(store.archive_path(), "## Work\n- [x] older id:cccc\n").unwrap();
 
-        let todos = store.load_all().unwrap();
-        assert_eq!(todos.len(), 1);
-        assert_eq!(todos[0].project, "Work");
+        store.archive(None).unwrap();
+        store.undo(1).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(
+            fs::read_to_string(store.archive_path()).unwrap(),
+            "## Work\n- [x] older id:cccc\n"
+        );
     }
 
     #[test]
-    fn load_all_ignores_pomodoro_lines() {
+    fn concurrent_adds_are_not_lost() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(
-            &dir,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n- [x] done\n",
-        );
+        let (_, path) = store_with_content(&dir, "## Work\n");
+
+        let writers: Vec<_> = (0..4)
+            .map(|_| {
+                let store = TodoStore::new(path.clone());
+                std::thread::spawn(move || {
+                    for _ in 0..10 {
+                        store.add("Work", "task").unwrap();
+                    }
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
 
-        let todos = store.load_all().unwrap();
-        assert_eq!(todos.len(), 2);
+        assert_eq!(TodoStore::new(path).load_all().unwrap().len(), 40);
     }
 
     #[test]
-    fn load_all_returns_empty_for_missing_file() {
+    fn set_priority_errors_on_unknown_id() {
         let dir = TempDir::new().unwrap();
-        let store = TodoStore::new(dir.path().join("todos.md"));
-        assert!(store.load_all().unwrap().is_empty());
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] ship id:aaaa\n");
+
+        let result = store.set_priority("zzzz", Some(Priority::A));
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[test]
@@ -492,26 +3433,38 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [ ] first\n## Personal\n- [ ] second\n- [ ] third\n",
+            "## Work\n- [ ] first id:aaaa\n## Personal\n- [ ] second id:bbbb\n- [ ] third id:cccc\n",
         );
 
-        store.complete(1).unwrap();
+        store
+            .complete("bbbb", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] first\n## Personal\n- [x] second\n- [ ] third\n"
+            "## Work\n- [ ] first id:aaaa\n## Personal\n- [x] second id:bbbb\n  - ✅ 2026-10-17 15:42\n- [ ] third id:cccc\n"
         );
     }
 
     #[test]
-    fn complete_errors_on_out_of_bounds() {
+    fn complete_errors_on_unknown_id() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one id:aaaa\n");
+
+        let result = store.complete("zzzz", datetime(2026, 10, 17, 15, 42));
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn complete_errors_on_done_task() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one\n");
+        let (store, _) = store_with_content(&dir, "## Work\n- [x] finished id:aaaa\n");
 
-        let result = store.complete(5);
+        let result = store.complete("aaaa", datetime(2026, 10, 17, 15, 42));
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("out of bounds"));
+        assert!(result.unwrap_err().to_string().contains("already complete"));
     }
 
     #[test]
@@ -519,33 +3472,125 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n- [ ] other\n",
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n- [ ] other id:bbbb\n",
+        );
+
+        store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [x] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - ✅ 2026-10-17 15:42\n- [ ] other id:bbbb\n"
         );
+    }
+
+    #[test]
+    fn complete_is_unaffected_by_tasks_inserted_before_target() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) =
+            store_with_content(&dir, "## Work\n- [ ] first id:aaaa\n- [ ] second id:bbbb\n");
 
-        store.complete(0).unwrap();
+        fs::write(
+            &path,
+            "## Work\n- [ ] inserted id:cccc\n- [ ] first id:aaaa\n- [ ] second id:bbbb\n",
+        )
+        .unwrap();
+        store
+            .complete("bbbb", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [x] task\n  - 🍅 2026-02-12 10:00\n- [ ] other\n"
+            "## Work\n- [ ] inserted id:cccc\n- [ ] first id:aaaa\n- [x] second id:bbbb\n  - ✅ 2026-10-17 15:42\n"
         );
     }
 
     #[test]
-    fn complete_skips_done_todos_when_counting() {
+    fn complete_regenerates_recurring_task() {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [x] done\n- [ ] first open\n- [ ] second open\n",
+            "## Chores\n- [ ] review deps pri:B due:2026-10-16 every:week id:aaaa\n  - 🍅 2026-10-16 10:00\n- [ ] other id:bbbb\n",
         );
 
-        store.complete(1).unwrap();
+        let completion = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        let next = completion.next.unwrap();
+        assert_eq!(next.description, "review deps");
+        assert_eq!(next.due, Some(date(2026, 10, 23)));
+        assert_eq!(next.priority, Some(Priority::B));
+        assert_ne!(next.id, "aaaa");
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [x] done\n- [ ] first open\n- [x] second open\n"
+            format!(
+                "## Chores\n- [x] review deps pri:B due:2026-10-16 id:aaaa\n  - 🍅 2026-10-16 10:00\n  - ✅ 2026-10-17 15:42\n- [ ] review deps pri:B due:2026-10-23 every:week id:{}\n- [ ] other id:bbbb\n",
+                next.id
+            )
+        );
+    }
+
+    #[test]
+    fn complete_recurring_task_without_due_date_counts_from_today() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) =
+            store_with_content(&dir, "## Home\n- [ ] pay rent every:month on 1st id:aaaa\n");
+
+        let completion = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        assert_eq!(completion.next.unwrap().due, Some(date(2026, 11, 1)));
+    }
+
+    #[test]
+    fn complete_recurring_task_keeps_the_estimate() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] review every:week est:3🍅 id:aaaa\n  - 🍅 2026-10-17 10:00\n",
         );
+
+        let next = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap()
+            .next
+            .unwrap();
+
+        assert_eq!(next.estimate, Some(3));
+        assert_eq!(next.pomodoros, (0, 0));
+        assert!(fs::read_to_string(&path).unwrap().contains(&format!(
+            "- [ ] review due:2026-10-24 every:week est:3🍅 id:{}",
+            next.id
+        )));
+    }
+
+    #[test]
+    fn complete_recurring_task_out_of_range_changes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let content = "## Home\n- [ ] someday every:4000000000d id:aaaa\n";
+        let (store, path) = store_with_content(&dir, content);
+
+        let result = store.complete("aaaa", datetime(2026, 10, 17, 15, 42));
+        assert!(result.unwrap_err().to_string().contains("out of range"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), content);
+    }
+
+    #[test]
+    fn complete_plain_task_has_no_next_occurrence() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] once id:aaaa\n");
+
+        let completion = store
+            .complete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert!(completion.next.is_none());
     }
 
     fn datetime(year: i32, month: u32, day: u32, hour: u32, min: u32) -> NaiveDateTime {
@@ -558,32 +3603,41 @@ mod tests {
     #[test]
     fn add_pomodoro_inserts_under_correct_todo() {
         let dir = TempDir::new().unwrap();
-        let (store, path) = store_with_content(&dir, "## Work\n- [ ] first\n- [ ] second\n");
+        let (store, path) =
+            store_with_content(&dir, "## Work\n- [ ] first id:aaaa\n- [ ] second id:bbbb\n");
 
         store
-            .add_pomodoro(0, datetime(2026, 2, 12, 10, 0), false)
+            .add_pomodoro(
+                &store.target("aaaa").unwrap(),
+                datetime(2026, 2, 12, 10, 0),
+                false,
+            )
             .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] first\n  - 🍅 2026-02-12 10:00\n- [ ] second\n"
+            "## Work\n- [ ] first id:aaaa\n  - 🍅 2026-02-12 10:00\n- [ ] second id:bbbb\n"
         );
     }
 
     #[test]
     fn add_pomodoro_cancelled() {
         let dir = TempDir::new().unwrap();
-        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task\n");
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] task id:aaaa\n");
 
         store
-            .add_pomodoro(0, datetime(2026, 2, 12, 14, 30), true)
+            .add_pomodoro(
+                &store.target("aaaa").unwrap(),
+                datetime(2026, 2, 12, 14, 30),
+                true,
+            )
             .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 14:30 cancelled\n"
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 14:30 cancelled\n"
         );
     }
 
@@ -592,58 +3646,143 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n- [ ] other\n",
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n- [ ] other id:bbbb\n",
         );
 
         store
-            .add_pomodoro(0, datetime(2026, 2, 12, 11, 0), false)
+            .add_pomodoro(
+                &store.target("aaaa").unwrap(),
+                datetime(2026, 2, 12, 11, 0),
+                false,
+            )
             .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n  - 🍅 2026-02-12 11:00\n- [ ] other\n"
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - 🍅 2026-02-12 11:00\n- [ ] other id:bbbb\n"
         );
     }
 
     #[test]
     fn add_pomodoro_across_projects() {
         let dir = TempDir::new().unwrap();
-        let (store, path) =
-            store_with_content(&dir, "## Work\n- [ ] task 1\n## Personal\n- [ ] task 2\n");
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] task 1 id:aaaa\n## Personal\n- [ ] task 2 id:bbbb\n",
+        );
+
+        store
+            .add_pomodoro(
+                &store.target("bbbb").unwrap(),
+                datetime(2026, 2, 12, 9, 0),
+                false,
+            )
+            .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            content,
+            "## Work\n- [ ] task 1 id:aaaa\n## Personal\n- [ ] task 2 id:bbbb\n  - 🍅 2026-02-12 09:00\n"
+        );
+    }
 
+    #[test]
+    fn add_pomodoro_follows_task_after_external_edit() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] focus id:aaaa\n");
+        let target = store.target("aaaa").unwrap();
+
+        fs::write(
+            &path,
+            "## Work\n- [ ] inserted id:bbbb\n## Later\n- [ ] focus pri:A id:aaaa\n",
+        )
+        .unwrap();
         store
-            .add_pomodoro(1, datetime(2026, 2, 12, 9, 0), false)
+            .add_pomodoro(&target, datetime(2026, 10, 17, 9, 0), false)
             .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] task 1\n## Personal\n- [ ] task 2\n  - 🍅 2026-02-12 09:00\n"
+            "## Work\n- [ ] inserted id:bbbb\n## Later\n- [ ] focus pri:A id:aaaa\n  - 🍅 2026-10-17 09:00\n"
         );
     }
 
     #[test]
-    fn add_pomodoro_errors_on_out_of_bounds() {
+    fn add_pomodoro_finds_task_whose_id_was_removed() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] focus id:aaaa\n");
+        let target = store.target("aaaa").unwrap();
+
+        fs::write(&path, "## Work\n- [ ] other id:bbbb\n- [ ] focus\n").unwrap();
+        store
+            .add_pomodoro(&target, datetime(2026, 10, 17, 9, 0), false)
+            .unwrap();
+
+        let todos = store.load_all().unwrap();
+        assert_eq!(todos[1].description, "focus");
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.ends_with("  - 🍅 2026-10-17 09:00\n"));
+    }
+
+    #[test]
+    fn add_pomodoro_refuses_when_task_cannot_be_identified() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] focus id:aaaa\n");
+        let target = store.target("aaaa").unwrap();
+
+        let edited = "## Work\n- [ ] focus\n- [ ] focus\n";
+        fs::write(&path, edited).unwrap();
+        let result = store.add_pomodoro(&target, datetime(2026, 10, 17, 9, 0), false);
+
+        let message = format!("{:#}", result.unwrap_err());
+        assert!(message.contains("not recorded"));
+        assert!(message.contains("ambiguous"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+
+        fs::write(&path, "## Work\n").unwrap();
+        let result = store.add_pomodoro(&target, datetime(2026, 10, 17, 9, 0), false);
+        assert!(format!("{:#}", result.unwrap_err()).contains("gone"));
+    }
+
+    #[test]
+    fn add_pomodoro_refuses_other_tasks_with_the_same_description() {
+        let dir = TempDir::new().unwrap();
+        let original = "## Work\n- [x] standup id:aaaa\n- [ ] standup every:day id:bbbb\n";
+        let (store, path) = store_with_content(&dir, original);
+        let target = store.target("bbbb").unwrap();
+
+        store.delete("bbbb", datetime(2026, 10, 17, 9, 10)).unwrap();
+        let deleted = fs::read_to_string(&path).unwrap();
+        let result = store.add_pomodoro(&target, datetime(2026, 10, 17, 9, 0), false);
+
+        assert!(format!("{:#}", result.unwrap_err()).contains("gone"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), deleted);
+    }
+
+    #[test]
+    fn target_errors_on_unknown_id() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one\n");
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one id:aaaa\n");
 
-        let result = store.add_pomodoro(5, datetime(2026, 2, 12, 10, 0), false);
+        let result = store.target("zzzz");
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("out of bounds"));
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[test]
     fn add_note_inserts_under_correct_todo() {
         let dir = TempDir::new().unwrap();
-        let (store, path) = store_with_content(&dir, "## Work\n- [ ] first\n- [ ] second\n");
+        let (store, path) =
+            store_with_content(&dir, "## Work\n- [ ] first id:aaaa\n- [ ] second id:bbbb\n");
 
-        store.add_note(0, "important detail").unwrap();
+        store.add_note("aaaa", "important detail").unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] first\n  - 📝 important detail\n- [ ] second\n"
+            "## Work\n- [ ] first id:aaaa\n  - 📝 important detail\n- [ ] second id:bbbb\n"
         );
     }
 
@@ -652,15 +3791,15 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n- [ ] other\n",
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n- [ ] other id:bbbb\n",
         );
 
-        store.add_note(0, "a note").unwrap();
+        store.add_note("aaaa", "a note").unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n  - 📝 a note\n- [ ] other\n"
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - 📝 a note\n- [ ] other id:bbbb\n"
         );
     }
 
@@ -669,38 +3808,45 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n  - 📝 first note\n- [ ] other\n",
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - 📝 first note\n- [ ] other id:bbbb\n",
         );
 
-        store.add_note(0, "second note").unwrap();
+        store.add_note("aaaa", "second note").unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(
             content,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n  - 📝 first note\n  - 📝 second note\n- [ ] other\n"
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - 📝 first note\n  - 📝 second note\n- [ ] other id:bbbb\n"
         );
     }
 
     #[test]
-    fn add_note_errors_on_out_of_bounds() {
+    fn add_note_errors_on_unknown_id() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one\n");
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one id:aaaa\n");
 
-        let result = store.add_note(5, "note");
+        let result = store.add_note("zzzz", "note");
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("out of bounds"));
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[test]
     fn delete_removes_todo_line() {
         let dir = TempDir::new().unwrap();
-        let (store, path) =
-            store_with_content(&dir, "## Work\n- [ ] first\n- [ ] second\n- [ ] third\n");
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] first id:aaaa\n- [ ] second id:bbbb\n- [ ] third id:cccc\n",
+        );
 
-        store.delete(1).unwrap();
+        store
+            .delete("bbbb", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Work\n- [ ] first\n- [ ] third\n");
+        assert_eq!(
+            content,
+            "## Work\n- [ ] first id:aaaa\n- [ ] third id:cccc\n"
+        );
     }
 
     #[test]
@@ -708,23 +3854,156 @@ mod tests {
         let dir = TempDir::new().unwrap();
         let (store, path) = store_with_content(
             &dir,
-            "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n  - 📝 a note\n- [ ] other\n",
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - 📝 a note\n- [ ] other id:bbbb\n",
         );
 
-        store.delete(0).unwrap();
+        store
+            .delete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
-        assert_eq!(content, "## Work\n- [ ] other\n");
+        assert_eq!(content, "## Work\n- [ ] other id:bbbb\n");
+    }
+
+    #[test]
+    fn delete_moves_task_to_trash() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(
+            &dir,
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n- [ ] other id:bbbb\n",
+        );
+
+        store
+            .delete("aaaa", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(store.trash_path()).unwrap(),
+            "## Work\n- [ ] task id:aaaa\n  - 🍅 2026-02-12 10:00\n  - 🗑 2026-10-17 15:42\n"
+        );
+        let trashed = store.trashed().unwrap();
+        assert_eq!(trashed.len(), 1);
+        assert_eq!(trashed[0].deleted, Some(datetime(2026, 10, 17, 15, 42)));
+    }
+
+    #[test]
+    fn restore_puts_subtask_back_under_its_parent() {
+        let dir = TempDir::new().unwrap();
+        let original = "## Work\n- [ ] parent id:aaaa\n  - [ ] child id:bbbb\n    - 📝 note\n";
+        let (store, path) = store_with_content(&dir, original);
+
+        store
+            .delete("bbbb", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Work\n- [ ] parent id:aaaa\n"
+        );
+
+        let todo = store.restore("bbbb").unwrap();
+        assert_eq!(todo.parent.as_deref(), Some("aaaa"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert_eq!(fs::read_to_string(store.trash_path()).unwrap(), "");
+    }
+
+    #[test]
+    fn restore_project_recreates_deleted_project() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(
+            &dir,
+            "## Work\n- [ ] one id:aaaa\n- [x] two id:bbbb\n## Home\n- [ ] three id:cccc\n",
+        );
+
+        store
+            .delete_project("Work", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+        assert_eq!(store.projects().unwrap(), vec!["Home"]);
+        assert_eq!(store.trashed().unwrap().len(), 2);
+
+        let restored = store.restore_project("Work").unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "## Home\n- [ ] three id:cccc\n## Work\n- [ ] one id:aaaa\n- [x] two id:bbbb\n"
+        );
+        assert!(store.restore_project("Work").is_err());
+    }
+
+    #[test]
+    fn restore_renumbers_ids_taken_in_the_meantime() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n");
+        fs::write(
+            store.trash_path(),
+            "## Work\n- [ ] old id:aaaa\n  - 🗑 2026-10-17 15:42\n",
+        )
+        .unwrap();
+        fs::write(&path, "## Work\n- [ ] new id:aaaa\n").unwrap();
+
+        let todo = store.restore("aaaa").unwrap();
+
+        assert_eq!(todo.description, "old");
+        assert_ne!(todo.id, "aaaa");
+        assert_eq!(store.open_todos().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn new_ids_avoid_archived_and_trashed_ones() {
+        let dir = TempDir::new().unwrap();
+        let (store, path) = store_with_content(&dir, "## Work\n- [ ] open id:aaaa\n");
+        fs::write(store.archive_path(), "## Work\n- [x] done id:bbbb\n").unwrap();
+        fs::write(
+            store.trash_path(),
+            "## Work\n- [ ] old id:cccc\n  - 🗑 2026-10-17 15:42\n- [ ] older id:bbbb\n  - 🗑 2026-10-17 15:42\n",
+        )
+        .unwrap();
+
+        let doc = Document::parse(&fs::read_to_string(&path).unwrap());
+        let taken = store.taken_ids(&doc).unwrap();
+        assert_eq!(taken.len(), 3);
+
+        // A restored task whose ID is in the archive gets a new one
+        let todo = store.restore("bbbb").unwrap();
+        assert_eq!(todo.description, "older");
+        assert!(!taken.contains(&todo.id));
+    }
+
+    #[test]
+    fn restore_errors_on_unknown_id() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) = store_with_content(&dir, "## Work\n");
+
+        let result = store.restore("zzzz");
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("not found in the trash"));
+    }
+
+    #[test]
+    fn empty_trash_removes_trashed_tasks() {
+        let dir = TempDir::new().unwrap();
+        let (store, _) =
+            store_with_content(&dir, "## Work\n- [ ] one id:aaaa\n- [ ] two id:bbbb\n");
+        store
+            .delete_project("Work", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
+
+        assert_eq!(store.empty_trash().unwrap(), 2);
+        assert!(store.trashed().unwrap().is_empty());
+
+        store.undo(1).unwrap();
+        assert_eq!(store.trashed().unwrap().len(), 2);
     }
 
     #[test]
-    fn delete_errors_on_out_of_bounds() {
+    fn delete_errors_on_unknown_id() {
         let dir = TempDir::new().unwrap();
-        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one\n");
+        let (store, _) = store_with_content(&dir, "## Work\n- [ ] only one id:aaaa\n");
 
-        let result = store.delete(5);
+        let result = store.delete("zzzz", datetime(2026, 10, 17, 15, 42));
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("out of bounds"));
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[test]
@@ -735,7 +4014,9 @@ mod tests {
             "## Work\n- [ ] task\n  - 🍅 2026-02-12 10:00\n## Personal\n- [ ] task\n",
         );
 
-        store.delete_project("Work").unwrap();
+        store
+            .delete_project("Work", datetime(2026, 10, 17, 15, 42))
+            .unwrap();
 
         let content = fs::read_to_string(&path).unwrap();
         assert_eq!(content, "## Personal\n- [ ] task\n");